@@ -1,29 +1,122 @@
 mod tools;
 
-use crate::config::LLMConfig;
+use crate::config::{LLMConfig, SearchConfig};
+use crate::db::search_cache_service::SearchCacheService;
 use crate::db::user_model::User;
 use crate::scheduler::SchedulerManager;
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use milky_rust_sdk::MilkyClient;
-use rig::agent::AgentBuilder;
+use rig::agent::{AgentBuilder, CancelSignal, PromptHook};
 use rig::client::CompletionClient;
-use rig::completion::{Chat};
+use rig::completion::Prompt;
 use rig::providers::openai;
+use rig::tool::{Tool, ToolDyn};
 use std::sync::Arc;
-use tools::{CreateScheduledTask, GetCurrentTime, SendMessage, WebSearch};
+use tokio::sync::Mutex;
+use tools::{
+    CancelScheduledTask, CreateReminder, CreateScheduledTask, FetchUrl, GetCurrentTime,
+    ListScheduledTasks, PauseScheduledTask, ResumeScheduledTask, SendGroupMessage, SendMessage,
+    SetUserTimezone, UpdateScheduledTask, WebSearch,
+};
+
+/// 群配置可以限制的工具，回复用的 send_group_message 始终可用
+pub const GROUP_TOOL_NAMES: [&str; 3] = [GetCurrentTime::NAME, WebSearch::NAME, FetchUrl::NAME];
 
 pub struct AgentTask {
+    pub task_id: Option<i64>,
     pub target_user_id: i64,
     pub content: String,
+    pub scheduled_at: DateTime<Utc>,
+    pub attempt: u32,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DealError {
+    #[error("LLM error: {0}")]
+    Llm(String),
+    #[error("Send error: {0}")]
+    Send(String),
+}
+
+/// 记录本轮对话中 send_message 或 send_group_message 的投递结果
+#[derive(Clone, Default)]
+struct DeliveryHook {
+    state: Arc<Mutex<DeliveryState>>,
+}
+
+#[derive(Default)]
+struct DeliveryState {
+    sent: bool,
+    error: Option<String>,
+    message_seqs: Vec<i64>,
+}
+
+impl PromptHook<openai::CompletionModel> for DeliveryHook {
+    async fn on_tool_result(
+        &self,
+        tool_name: &str,
+        _tool_call_id: Option<String>,
+        _args: &str,
+        result: &str,
+        _cancel_sig: CancelSignal,
+    ) {
+        if tool_name != SendMessage::NAME && tool_name != SendGroupMessage::NAME {
+            return;
+        }
+
+        let mut state = self.state.lock().await;
+        let value = serde_json::from_str::<serde_json::Value>(result).ok();
+        let success = value
+            .as_ref()
+            .and_then(|value| value.get("success").and_then(|s| s.as_bool()))
+            .unwrap_or(false);
+
+        if success {
+            state.sent = true;
+            let seqs = value
+                .as_ref()
+                .and_then(|value| value.get("message_seqs"))
+                .and_then(|seqs| seqs.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|seq| seq.as_i64());
+            state.message_seqs.extend(seqs);
+        } else {
+            state.error = Some(result.to_string());
+        }
+    }
 }
 
 pub struct Agent {
-    agent: rig::agent::Agent<openai::CompletionModel>,
+    model: openai::CompletionModel,
+    system_prompt: String,
+    temperature: f64,
+    client: Arc<MilkyClient>,
+    scheduler_manager: Arc<SchedulerManager>,
+    web_search: WebSearch,
+    fetch_url: FetchUrl,
+}
+
+/// 群消息的来源信息，会作为上下文提供给模型
+pub struct GroupContext {
+    pub group_id: i64,
+    pub group_name: String,
+    pub sender_card: String,
+    /// 群里最近的消息，按时间顺序排列
+    pub history: Vec<String>,
+    pub persona: Option<String>,
+    /// 群配置允许使用的工具，为空表示不限制
+    pub allowed_tools: Option<Vec<String>>,
+    /// 为 true 时消息并未直接叫到机器人，由模型自行判断是否需要发言
+    pub proactive: bool,
 }
 
 impl Agent {
     pub fn new(
         config: &LLMConfig,
+        search_config: &SearchConfig,
+        search_cache_service: SearchCacheService,
         client: Arc<MilkyClient>,
         scheduler_manager: Arc<SchedulerManager>,
     ) -> Result<Self> {
@@ -36,34 +129,199 @@ impl Agent {
 
         let system_prompt = config.system_prompt()?;
 
-        let agent = AgentBuilder::new(model)
-            .preamble(&system_prompt)
+        Ok(Self {
+            model,
+            system_prompt,
+            temperature: config.temperature,
+            client,
+            scheduler_manager,
+            web_search: WebSearch::new(search_config, search_cache_service)?,
+            fetch_url: FetchUrl::new()?,
+        })
+    }
+
+    /// 每次对话都重新创建工具，任务相关的工具绑定当前用户，
+    /// 调用者身份由服务端决定而不是让模型在参数里填写
+    fn private_tools(&self, user: &User) -> Vec<Box<dyn ToolDyn>> {
+        let manager = &self.scheduler_manager;
+        let user_id = user.id;
+        vec![
+            Box::new(GetCurrentTime::new(manager.timezone_of(user))),
+            Box::new(SendMessage::new(Arc::clone(&self.client))),
+            Box::new(CreateReminder::new(Arc::clone(manager), user_id)),
+            Box::new(CreateScheduledTask::new(Arc::clone(manager), user_id)),
+            Box::new(ListScheduledTasks::new(Arc::clone(manager), user_id)),
+            Box::new(CancelScheduledTask::new(Arc::clone(manager), user_id)),
+            Box::new(PauseScheduledTask::new(Arc::clone(manager), user_id)),
+            Box::new(ResumeScheduledTask::new(Arc::clone(manager), user_id)),
+            Box::new(UpdateScheduledTask::new(Arc::clone(manager), user_id)),
+            Box::new(SetUserTimezone::new(Arc::clone(manager), user_id)),
+            Box::new(self.web_search.clone()),
+            Box::new(self.fetch_url.clone()),
+        ]
+    }
+
+    /// 群里任何人都可以叫到机器人，只提供不涉及私聊和定时任务的工具
+    fn group_tools(&self, group_id: i64, user_id: i64) -> Vec<Box<dyn ToolDyn>> {
+        vec![
+            Box::new(GetCurrentTime::new(
+                self.scheduler_manager.default_timezone(),
+            )),
+            Box::new(SendGroupMessage::new(
+                Arc::clone(&self.client),
+                group_id,
+                user_id,
+            )),
+            Box::new(self.web_search.clone()),
+            Box::new(self.fetch_url.clone()),
+        ]
+    }
+
+    fn build(&self, tools: Vec<Box<dyn ToolDyn>>) -> rig::agent::Agent<openai::CompletionModel> {
+        AgentBuilder::new(self.model.clone())
+            .preamble(&self.system_prompt)
             .default_max_depth(5)
-            .temperature(config.temperature)
-            .tool(GetCurrentTime)
-            .tool(SendMessage::new(client))
-            .tool(CreateScheduledTask::new(scheduler_manager))
-            .tool(WebSearch::new())
-            .build();
-
-        Ok(Self { agent })
+            .temperature(self.temperature)
+            .tools(tools)
+            .build()
+    }
+
+    /// 处理一条消息，成功时返回发送给用户的消息序列号
+    ///
+    /// context 为定时任务创建时保存的对话背景，帮助提醒触发时衔接之前的对话
+    pub async fn deal(
+        &self,
+        user: &User,
+        message: &str,
+        context: Option<&str>,
+    ) -> Result<Vec<i64>, DealError> {
+        let mut prompt = self.user_info(user);
+
+        if let Some(context) = context {
+            prompt.push_str(&format!(
+                "\ncontext（创建这条提醒时的对话背景，提醒时可以自然地提及）: {}\n",
+                context
+            ));
+        }
+
+        prompt.push_str(&format!("\ncontent: {}", message));
+
+        self.run(self.private_tools(user), &prompt, DeliveryHook::default())
+            .await
+    }
+
+    /// 处理陌生人的临时会话消息，只允许查询时间和回复对方，避免陌生人创建提醒、调用搜索或让机器人私聊其他人
+    pub async fn deal_limited(&self, user: &User, message: &str) -> Result<Vec<i64>, DealError> {
+        let mut prompt = self.user_info(user);
+        prompt.push_str(
+            "\n对方是通过群临时会话联系你的陌生人，请调用 send_message 简短、礼貌地回复，不要答应创建提醒或执行其他操作\n",
+        );
+        prompt.push_str(&format!("\ncontent: {}", message));
+
+        let tools: Vec<Box<dyn ToolDyn>> = vec![
+            Box::new(GetCurrentTime::new(
+                self.scheduler_manager.timezone_of(user),
+            )),
+            Box::new(SendMessage::to_user(Arc::clone(&self.client), user.id)),
+        ];
+        self.run(tools, &prompt, DeliveryHook::default()).await
+    }
+
+    /// 处理一条触发了机器人的群消息，回复通过 send_group_message 发送到群里
+    pub async fn deal_group(
+        &self,
+        user: &User,
+        group: &GroupContext,
+        message: &str,
+    ) -> Result<Vec<i64>, DealError> {
+        let mut prompt = self.user_info(user);
+        prompt.push_str(&format!(
+            "- Group: {} ({})\n- Group Card: {}\n",
+            group.group_name, group.group_id, group.sender_card
+        ));
+        if let Some(persona) = &group.persona {
+            prompt.push_str(&format!("- Group Persona: {}\n", persona));
+        }
+        prompt.push_str(&format!(
+            "\n这条消息来自群聊，请调用 send_group_message 回复到群 {}，群聊中不能创建提醒或私聊用户，需要时请对方私聊你\n",
+            group.group_id
+        ));
+        if group.proactive {
+            prompt.push_str(
+                "这条消息并没有叫你，只有在你能提供明确帮助或话题与你相关时才发言，否则不要调用任何工具，直接结束\n",
+            );
+        }
+        if !group.history.is_empty() {
+            prompt.push_str(&format!(
+                "\n群聊最近的消息（按时间顺序）:\n{}\n",
+                group.history.join("\n")
+            ));
+        }
+        prompt.push_str(&format!("\ncontent: {}", message));
+
+        // 群配置限制了可用工具时只注册这些工具，回复用的 send_group_message 始终保留
+        let tools = self
+            .group_tools(group.group_id, user.id)
+            .into_iter()
+            .filter(|tool| {
+                let name = tool.name();
+                name == SendGroupMessage::NAME
+                    || group
+                        .allowed_tools
+                        .as_ref()
+                        .is_none_or(|allowed| allowed.contains(&name))
+            })
+            .collect();
+
+        let hook = DeliveryHook::default();
+        match self.run(tools, &prompt, hook.clone()).await {
+            Err(DealError::Send(_))
+                if group.proactive && hook.state.lock().await.error.is_none() =>
+            {
+                Ok(Vec::new())
+            }
+            result => result,
+        }
     }
 
-    pub async fn deal(&self, user: &User, message: &str) -> Result<()> {
-        let relation_str = user.relation.as_str();
+    fn user_info(&self, user: &User) -> String {
+        let timezone = self.scheduler_manager.timezone_of(user);
 
-        let mut prompt = format!(
-            "Info:\n- ID: {}\n- Name: {}\n- Relation: {}\n",
-            user.id, user.name, relation_str
+        let mut info = format!(
+            "Info:\n- ID: {}\n- Name: {}\n- Relation: {}\n- Timezone: {}\n",
+            user.id,
+            user.name,
+            user.relation.as_str(),
+            timezone
         );
 
         if let Some(custom_prompt) = &user.custom_prompt {
-            prompt.push_str(&format!("- Custom Prompt: {}\n", custom_prompt));
+            info.push_str(&format!("- Custom Prompt: {}\n", custom_prompt));
         }
 
-        prompt.push_str(&format!("\ncontent: {}", message));
+        info
+    }
+
+    async fn run(
+        &self,
+        tools: Vec<Box<dyn ToolDyn>>,
+        prompt: &str,
+        hook: DeliveryHook,
+    ) -> Result<Vec<i64>, DealError> {
+        let _response: String = self
+            .build(tools)
+            .prompt(prompt)
+            .with_hook(hook.clone())
+            .await
+            .map_err(|e| DealError::Llm(e.to_string()))?;
+
+        let state = hook.state.lock().await;
+        if state.sent {
+            return Ok(state.message_seqs.clone());
+        }
 
-        let _response: String = self.agent.chat(&prompt, vec![]).await?;
-        Ok(())
+        Err(DealError::Send(state.error.clone().unwrap_or_else(|| {
+            "未调用 send_message 发送任何消息".to_string()
+        })))
     }
 }
@@ -1,9 +1,27 @@
+pub mod cancel_scheduled_task;
+pub mod create_reminder;
 pub mod create_scheduled_task;
+pub mod fetch_url;
 pub mod get_current_time;
+pub mod list_scheduled_tasks;
+pub mod pause_scheduled_task;
+pub mod resume_scheduled_task;
+pub mod send_group_message;
 pub mod send_message;
+pub mod set_user_timezone;
+pub mod update_scheduled_task;
 pub mod web_search;
 
+pub use cancel_scheduled_task::CancelScheduledTask;
+pub use create_reminder::CreateReminder;
 pub use create_scheduled_task::CreateScheduledTask;
+pub use fetch_url::FetchUrl;
 pub use get_current_time::GetCurrentTime;
+pub use list_scheduled_tasks::ListScheduledTasks;
+pub use pause_scheduled_task::PauseScheduledTask;
+pub use resume_scheduled_task::ResumeScheduledTask;
+pub use send_group_message::SendGroupMessage;
 pub use send_message::SendMessage;
+pub use set_user_timezone::SetUserTimezone;
+pub use update_scheduled_task::UpdateScheduledTask;
 pub use web_search::WebSearch;
@@ -0,0 +1,83 @@
+use crate::scheduler::SchedulerManager;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::debug;
+
+#[derive(Deserialize)]
+pub struct CancelScheduledTaskArgs {
+    pub task_id: i64,
+}
+
+#[derive(Serialize)]
+pub struct CancelScheduledTaskResult {
+    pub success: bool,
+    pub task_id: i64,
+    pub message: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Cancel scheduled task error: {0}")]
+pub struct CancelScheduledTaskError(String);
+
+pub struct CancelScheduledTask {
+    manager: Arc<SchedulerManager>,
+    user_id: i64,
+}
+
+impl CancelScheduledTask {
+    pub fn new(manager: Arc<SchedulerManager>, user_id: i64) -> Self {
+        Self { manager, user_id }
+    }
+}
+
+impl Tool for CancelScheduledTask {
+    const NAME: &'static str = "cancel_scheduled_task";
+    type Error = CancelScheduledTaskError;
+    type Args = CancelScheduledTaskArgs;
+    type Output = CancelScheduledTaskResult;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "永久取消并删除用户的一个定时任务/日程提醒。如果不确定任务ID，请先调用 list_scheduled_tasks 查询。取消后需要告知用户。"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "task_id": {
+                        "type": "integer",
+                        "description": "要取消的任务ID"
+                    }
+                },
+                "required": ["task_id"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        debug!(
+            "[Tool] cancel_scheduled_task called: user_id={}, task_id={}",
+            self.user_id, args.task_id
+        );
+
+        let task = self
+            .manager
+            .cancel_task(self.user_id, args.task_id)
+            .await
+            .map_err(|e| CancelScheduledTaskError(e.to_string()))?;
+
+        debug!(
+            "[Tool] cancel_scheduled_task completed: task_id={}",
+            task.id
+        );
+
+        Ok(CancelScheduledTaskResult {
+            success: true,
+            task_id: task.id,
+            message: format!("已取消定时任务：{}", task.content),
+        })
+    }
+}
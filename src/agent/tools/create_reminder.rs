@@ -0,0 +1,422 @@
+use crate::db::scheduler_model::{
+    CreateTaskRequest, MisfirePolicy, TaskCalendar, TaskCreator, TaskFrequency,
+};
+use crate::scheduler::SchedulerManager;
+use crate::scheduler::cron::{next_fire_time, once_cron};
+use crate::utils::{format_db_datetime, parse_db_datetime, parse_local_datetime};
+use chrono::{DateTime, NaiveTime, TimeDelta, Timelike, Utc};
+use chrono_tz::Tz;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::debug;
+
+const WEEKDAYS: &[&str] = &["MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+
+/// 相对时间最多一年，和一次性提醒的时间上限保持一致
+const MAX_RELATIVE_DAYS: i64 = 365;
+
+#[derive(Deserialize)]
+pub struct CreateReminderArgs {
+    pub target_user_id: Option<i64>,
+    pub content: String,
+    pub context: Option<String>,
+    pub at: Option<String>,
+    pub after: Option<RelativeOffset>,
+    pub repeat: Option<RecurrenceRule>,
+    pub misfire_policy: Option<String>,
+    pub max_retries: Option<i64>,
+    pub urgent: Option<bool>,
+    pub calendar: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RelativeOffset {
+    #[serde(default)]
+    pub days: i64,
+    #[serde(default)]
+    pub hours: i64,
+    #[serde(default)]
+    pub minutes: i64,
+}
+
+#[derive(Deserialize)]
+pub struct RecurrenceRule {
+    pub every: RepeatUnit,
+    pub time: String,
+    #[serde(default)]
+    pub weekdays: Vec<String>,
+    pub day_of_month: Option<u32>,
+    pub until: Option<String>,
+    pub count: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepeatUnit {
+    Day,
+    Weekday,
+    Week,
+    Month,
+}
+
+#[derive(Serialize)]
+pub struct CreateReminderResult {
+    pub success: bool,
+    pub task_id: i64,
+    pub frequency: String,
+    pub cron_expr: String,
+    pub timezone: String,
+    pub next_fire_time: String,
+    pub message: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Create reminder error: {0}")]
+pub struct CreateReminderError(String);
+
+pub struct CreateReminder {
+    manager: Arc<SchedulerManager>,
+    user_id: i64,
+}
+
+impl CreateReminder {
+    pub fn new(manager: Arc<SchedulerManager>, user_id: i64) -> Self {
+        Self { manager, user_id }
+    }
+
+    fn build_schedule(
+        args: &CreateReminderArgs,
+        timezone: Tz,
+    ) -> Result<(TaskFrequency, String, Option<DateTime<Tz>>), CreateReminderError> {
+        let now = Utc::now().with_timezone(&timezone);
+
+        match (&args.at, &args.after, &args.repeat) {
+            (Some(at), None, None) => {
+                let at = Self::parse_datetime(at, timezone)?;
+                if at <= now {
+                    return Err(CreateReminderError(format!(
+                        "提醒时间 {} 已经过去，当前时间为 {}",
+                        at.format("%Y-%m-%d %H:%M:%S"),
+                        now.format("%Y-%m-%d %H:%M:%S")
+                    )));
+                }
+                let at = at.with_nanosecond(0).unwrap_or(at);
+                Ok((TaskFrequency::Once, once_cron(&at), Some(at)))
+            }
+            (None, Some(after), None) => {
+                let invalid_offset = || {
+                    CreateReminderError(format!(
+                        "相对时间无效，必须大于 0 且不超过 {} 天",
+                        MAX_RELATIVE_DAYS
+                    ))
+                };
+                let offset = TimeDelta::try_days(after.days)
+                    .zip(TimeDelta::try_hours(after.hours))
+                    .zip(TimeDelta::try_minutes(after.minutes))
+                    .and_then(|((days, hours), minutes)| {
+                        days.checked_add(&hours)?.checked_add(&minutes)
+                    })
+                    .ok_or_else(invalid_offset)?;
+                if offset <= TimeDelta::zero()
+                    || TimeDelta::try_days(MAX_RELATIVE_DAYS).is_none_or(|max| offset > max)
+                {
+                    return Err(invalid_offset());
+                }
+                let at = now.checked_add_signed(offset).ok_or_else(invalid_offset)?;
+                let at = at.with_nanosecond(0).unwrap_or(at);
+                Ok((TaskFrequency::Once, once_cron(&at), Some(at)))
+            }
+            (None, None, Some(repeat)) => {
+                let (frequency, cron_expr) = Self::recurring_cron(repeat)?;
+                Ok((frequency, cron_expr, None))
+            }
+            _ => Err(CreateReminderError(
+                "at、after、repeat 三者必须且只能提供一个".to_string(),
+            )),
+        }
+    }
+
+    fn parse_datetime(value: &str, timezone: Tz) -> Result<DateTime<Tz>, CreateReminderError> {
+        parse_local_datetime(value, timezone).ok_or_else(|| {
+            CreateReminderError(format!(
+                "无法解析时间 '{}'，请使用 ISO 格式，例如 2025-01-01T08:30:00",
+                value
+            ))
+        })
+    }
+
+    fn parse_time(value: &str) -> Result<NaiveTime, CreateReminderError> {
+        NaiveTime::parse_from_str(value.trim(), "%H:%M")
+            .or_else(|_| NaiveTime::parse_from_str(value.trim(), "%H:%M:%S"))
+            .map_err(|_| {
+                CreateReminderError(format!("无法解析时刻 '{}'，请使用 HH:MM 格式", value))
+            })
+    }
+
+    fn recurring_cron(
+        repeat: &RecurrenceRule,
+    ) -> Result<(TaskFrequency, String), CreateReminderError> {
+        let time = Self::parse_time(&repeat.time)?;
+        let (second, minute, hour) = (time.second(), time.minute(), time.hour());
+
+        let frequency = match repeat.every {
+            RepeatUnit::Day => TaskFrequency::Daily,
+            RepeatUnit::Weekday => TaskFrequency::Weekdays,
+            RepeatUnit::Week => TaskFrequency::Weekly,
+            RepeatUnit::Month => TaskFrequency::Monthly,
+        };
+
+        let day_of_week = match repeat.every {
+            RepeatUnit::Day | RepeatUnit::Month => "*".to_string(),
+            RepeatUnit::Weekday => "MON-FRI".to_string(),
+            RepeatUnit::Week => {
+                if repeat.weekdays.is_empty() {
+                    return Err(CreateReminderError(
+                        "每周重复需要提供 weekdays，例如 [\"mon\", \"thu\"]".to_string(),
+                    ));
+                }
+                repeat
+                    .weekdays
+                    .iter()
+                    .map(|day| Self::parse_weekday(day))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(",")
+            }
+        };
+
+        let day_of_month = match repeat.every {
+            RepeatUnit::Month => match repeat.day_of_month {
+                Some(day) if (1..=31).contains(&day) => day.to_string(),
+                _ => {
+                    return Err(CreateReminderError(
+                        "每月重复需要提供 1-31 之间的 day_of_month".to_string(),
+                    ));
+                }
+            },
+            _ => "*".to_string(),
+        };
+
+        Ok((
+            frequency,
+            format!(
+                "{} {} {} {} * {}",
+                second, minute, hour, day_of_month, day_of_week
+            ),
+        ))
+    }
+
+    fn parse_weekday(value: &str) -> Result<&'static str, CreateReminderError> {
+        let upper = value.trim().to_ascii_uppercase();
+
+        WEEKDAYS
+            .iter()
+            .find(|day| upper.starts_with(*day))
+            .copied()
+            .ok_or_else(|| CreateReminderError(format!("无法解析星期 '{}'", value)))
+    }
+}
+
+impl Tool for CreateReminder {
+    const NAME: &'static str = "create_reminder";
+    type Error = CreateReminderError;
+    type Args = CreateReminderArgs;
+    type Output = CreateReminderResult;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "为用户创建提醒，推荐优先使用此工具而不是 create_scheduled_task。时间用结构化参数描述，at、after、repeat 三者选其一：at 为绝对时间，after 为从现在起的相对时间，repeat 为重复规则。时间均按用户所在时区解释。创建后请根据返回的 next_fire_time 向用户确认提醒时间。"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "target_user_id": {
+                        "type": "integer",
+                        "description": "可选，要提醒的用户ID，不提供则提醒创建者本人。只有 master 可以为其他用户创建"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "提醒内容的完整prompt，例如：'请用自然、友好的方式提醒用户该开会了'"
+                    },
+                    "context": {
+                        "type": "string",
+                        "description": "可选，创建提醒时的对话背景摘要，提醒触发时会一并提供，让提醒更自然。用一两句话概括用户说过的相关内容，例如：'用户说家里厨房漏水，打算给房东打电话'，不超过 500 字"
+                    },
+                    "at": {
+                        "type": "string",
+                        "description": "一次性提醒的绝对时间，ISO 格式，例如 '2025-03-01T08:30:00'"
+                    },
+                    "after": {
+                        "type": "object",
+                        "description": "一次性提醒的相对时间，例如 25 分钟后为 {\"minutes\": 25}",
+                        "properties": {
+                            "days": { "type": "integer" },
+                            "hours": { "type": "integer" },
+                            "minutes": { "type": "integer" }
+                        }
+                    },
+                    "repeat": {
+                        "type": "object",
+                        "description": "重复提醒规则，例如每个工作日 08:30 为 {\"every\": \"weekday\", \"time\": \"08:30\"}",
+                        "properties": {
+                            "every": {
+                                "type": "string",
+                                "enum": ["day", "weekday", "week", "month"],
+                                "description": "day 每天，weekday 每个工作日（周一至周五），week 每周指定几天，month 每月指定一天"
+                            },
+                            "time": {
+                                "type": "string",
+                                "description": "触发时刻，HH:MM 格式"
+                            },
+                            "weekdays": {
+                                "type": "array",
+                                "items": { "type": "string", "enum": ["mon", "tue", "wed", "thu", "fri", "sat", "sun"] },
+                                "description": "every 为 week 时必填"
+                            },
+                            "day_of_month": {
+                                "type": "integer",
+                                "description": "every 为 month 时必填，1-31"
+                            },
+                            "until": {
+                                "type": "string",
+                                "description": "可选，重复截止时间，ISO 格式，例如 '2025-06-30T23:59:00'"
+                            },
+                            "count": {
+                                "type": "integer",
+                                "description": "可选，总共提醒的次数，达到后自动停止"
+                            }
+                        },
+                        "required": ["every", "time"]
+                    },
+                    "misfire_policy": {
+                        "type": "string",
+                        "enum": ["run_immediately", "skip", "expire"],
+                        "description": "可选，机器人停机错过触发时的处理方式：run_immediately 恢复后立即补发（默认），skip 跳过本次，expire 直接结束任务"
+                    },
+                    "max_retries": {
+                        "type": "integer",
+                        "description": "可选，任务执行失败后自动重试的次数，不提供则使用默认值"
+                    },
+                    "urgent": {
+                        "type": "boolean",
+                        "description": "可选，是否为紧急提醒，紧急提醒在用户的安静时段和免打扰期间也会照常送达，默认 false"
+                    },
+                    "calendar": {
+                        "type": "string",
+                        "enum": ["any", "cn_workday"],
+                        "description": "可选，执行日历：any 按 cron 正常执行（默认），cn_workday 按中国法定工作日执行，跳过节假日并在调休上班的周末照常执行，只适用于 every 为 day 或 weekday 的重复提醒"
+                    }
+                },
+                "required": ["content"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        debug!(
+            "[Tool] create_reminder called: user_id={}, target_user_id={:?}, at={:?}, has_after={}, has_repeat={}",
+            self.user_id,
+            args.target_user_id,
+            args.at,
+            args.after.is_some(),
+            args.repeat.is_some()
+        );
+
+        let target_user_id = args.target_user_id.unwrap_or(self.user_id);
+        let timezone = self.manager.timezone_for(target_user_id).await;
+        let (frequency, cron_expr, once_at) = Self::build_schedule(&args, timezone)?;
+
+        let now = Utc::now().with_timezone(&timezone);
+        let next_fire =
+            next_fire_time(&cron_expr, &now).map_err(|e| CreateReminderError(e.to_string()))?;
+
+        if let Some(once_at) = once_at
+            && next_fire != once_at
+        {
+            return Err(CreateReminderError(
+                "一次性提醒的时间不能超过一年".to_string(),
+            ));
+        }
+
+        let misfire_policy = args
+            .misfire_policy
+            .as_deref()
+            .map(MisfirePolicy::from_str)
+            .transpose()
+            .map_err(|e| CreateReminderError(e.to_string()))?
+            .unwrap_or(MisfirePolicy::RunImmediately);
+
+        let calendar = args
+            .calendar
+            .as_deref()
+            .map(TaskCalendar::from_str)
+            .transpose()
+            .map_err(|e| CreateReminderError(e.to_string()))?;
+
+        let (end_at, max_runs) = match &args.repeat {
+            Some(repeat) => {
+                let end_at = repeat
+                    .until
+                    .as_deref()
+                    .map(|until| Self::parse_datetime(until, timezone))
+                    .transpose()?
+                    .map(|until| format_db_datetime(&until.with_timezone(&Utc)));
+                (end_at, repeat.count)
+            }
+            None => (None, None),
+        };
+
+        let req = CreateTaskRequest {
+            target_user_id,
+            frequency,
+            cron_expr: cron_expr.clone(),
+            content: args.content,
+            context: args.context,
+            created_by: TaskCreator::User,
+            creator_user_id: Some(self.user_id),
+            start_at: None,
+            end_at,
+            max_runs,
+            misfire_policy,
+            max_retries: args.max_retries,
+            urgent: args.urgent.unwrap_or(false),
+            calendar: calendar.unwrap_or(TaskCalendar::Any),
+        };
+
+        let task = self
+            .manager
+            .add_task(req)
+            .await
+            .map_err(|e| CreateReminderError(e.to_string()))?;
+
+        // 工作日日历的任务会跳过节假日，以调度器计算的下次执行时间为准
+        let next_fire = task
+            .next_run_at
+            .as_deref()
+            .and_then(parse_db_datetime)
+            .map(|t| t.with_timezone(&timezone))
+            .unwrap_or(next_fire);
+        let cron_expr = task.cron_expr.clone();
+        let next_fire_time = next_fire.format("%Y-%m-%d %H:%M:%S %A").to_string();
+        debug!(
+            "[Tool] create_reminder completed: task_id={}, cron={}, next_fire_time={}",
+            task.id, cron_expr, next_fire_time
+        );
+
+        Ok(CreateReminderResult {
+            success: true,
+            task_id: task.id,
+            frequency: frequency.as_str().to_string(),
+            cron_expr,
+            timezone: timezone.name().to_string(),
+            next_fire_time: next_fire_time.clone(),
+            message: format!(
+                "提醒已创建，下次触发时间为 {}（{}），请向用户确认",
+                next_fire_time, timezone
+            ),
+        })
+    }
+}
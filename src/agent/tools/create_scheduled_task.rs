@@ -1,5 +1,9 @@
-use crate::db::scheduler_model::{CreateTaskRequest, TaskCreator, TaskFrequency};
+use crate::db::scheduler_model::{
+    CreateTaskRequest, MisfirePolicy, TaskCalendar, TaskCreator, TaskFrequency,
+};
 use crate::scheduler::SchedulerManager;
+use crate::utils::{format_db_datetime, format_local_datetime, parse_local_datetime};
+use chrono::Utc;
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
@@ -9,16 +13,24 @@ use tracing::debug;
 
 #[derive(Deserialize)]
 pub struct CreateScheduledTaskArgs {
-    pub user_id: i64,
+    pub target_user_id: Option<i64>,
     pub content: String,
+    pub context: Option<String>,
     pub cron_expr: String,
     pub frequency: String,
+    pub end_at: Option<String>,
+    pub max_runs: Option<i64>,
+    pub misfire_policy: Option<String>,
+    pub max_retries: Option<i64>,
+    pub urgent: Option<bool>,
+    pub calendar: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct CreateScheduledTaskResult {
     pub success: bool,
     pub task_id: i64,
+    pub next_run_at: Option<String>,
     pub message: String,
 }
 
@@ -28,11 +40,12 @@ pub struct CreateScheduledTaskError(String);
 
 pub struct CreateScheduledTask {
     manager: Arc<SchedulerManager>,
+    user_id: i64,
 }
 
 impl CreateScheduledTask {
-    pub fn new(manager: Arc<SchedulerManager>) -> Self {
-        Self { manager }
+    pub fn new(manager: Arc<SchedulerManager>, user_id: i64) -> Self {
+        Self { manager, user_id }
     }
 }
 
@@ -45,49 +58,117 @@ impl Tool for CreateScheduledTask {
     async fn definition(&self, _prompt: String) -> ToolDefinition {
         ToolDefinition {
             name: Self::NAME.to_string(),
-            description: "为用户创建定时任务/日程提醒。可以创建一次性任务或每日重复任务。创建成功后需要告知用户任务已创建。"
+            description: "为用户创建定时任务/日程提醒。可以创建一次性任务，或按天、工作日、周、月及自定义 cron 重复的任务，并可设置截止时间或执行次数。创建成功后需要告知用户任务已创建。"
                 .to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
-                    "user_id": {
+                    "target_user_id": {
                         "type": "integer",
-                        "description": "要提醒的用户ID"
+                        "description": "可选，要提醒的用户ID，不提供则提醒创建者本人。只有 master 可以为其他用户创建"
                     },
                     "content": {
                         "type": "string",
                         "description": "提醒内容的完整prompt，例如：'请用自然、友好的方式提醒用户该开会了'"
                     },
+                    "context": {
+                        "type": "string",
+                        "description": "可选，创建提醒时的对话背景摘要，提醒触发时会一并提供，让提醒更自然。用一两句话概括用户说过的相关内容，例如：'用户说家里厨房漏水，打算给房东打电话'，不超过 500 字"
+                    },
                     "cron_expr": {
                         "type": "string",
-                        "description": "Cron表达式，格式为：秒 分 时 日 月 星期。例如：'0 0 8 * * *' 表示每天8点，'0 30 18 2 2 *' 表示2月2日18:30"
+                        "description": "Cron表达式，格式为：秒 分 时 日 月 星期。例如：'0 0 8 * * *' 表示每天8点，'0 30 18 2 2 *' 表示2月2日18:30。时间按用户所在时区解释"
                     },
                     "frequency": {
                         "type": "string",
-                        "enum": ["once", "daily"],
-                        "description": "任务频率：once表示执行一次，daily表示每天执行"
+                        "enum": ["once", "daily", "weekly", "monthly", "weekdays", "cron"],
+                        "description": "任务频率：once表示执行一次，daily表示每天执行，weekly表示每周执行，monthly表示每月执行，weekdays表示仅工作日执行，cron表示按自定义cron表达式重复执行。频率需要和cron_expr一致：daily的日、月、星期都为*，weekly需要指定星期且日为*，weekdays的星期为MON-FRI，monthly需要指定日且星期为*"
+                    },
+                    "end_at": {
+                        "type": "string",
+                        "description": "可选，重复任务的截止时间，ISO 格式，按用户所在时区解释，例如 '2025-06-30T23:59:00'"
+                    },
+                    "max_runs": {
+                        "type": "integer",
+                        "description": "可选，重复任务最多执行的次数，达到后自动停止"
+                    },
+                    "misfire_policy": {
+                        "type": "string",
+                        "enum": ["run_immediately", "skip", "expire"],
+                        "description": "可选，机器人停机错过触发时的处理方式：run_immediately 恢复后立即补发（默认），skip 跳过本次，expire 直接结束任务"
+                    },
+                    "max_retries": {
+                        "type": "integer",
+                        "description": "可选，任务执行失败后自动重试的次数，不提供则使用默认值"
+                    },
+                    "urgent": {
+                        "type": "boolean",
+                        "description": "可选，是否为紧急提醒，紧急提醒在用户的安静时段和免打扰期间也会照常送达，默认 false"
+                    },
+                    "calendar": {
+                        "type": "string",
+                        "enum": ["any", "cn_workday"],
+                        "description": "可选，执行日历：any 按 cron 正常执行（默认），cn_workday 按中国法定工作日执行，跳过节假日并在调休上班的周末照常执行，只适用于每天或工作日重复的任务"
                     }
                 },
-                "required": ["user_id", "content", "cron_expr", "frequency"]
+                "required": ["content", "cron_expr", "frequency"]
             }),
         }
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
         debug!(
-            "[Tool] create_scheduled_task called: user_id={}, frequency={}, cron={}",
-            args.user_id, args.frequency, args.cron_expr
+            "[Tool] create_scheduled_task called: user_id={}, target_user_id={:?}, frequency={}, cron={}",
+            self.user_id, args.target_user_id, args.frequency, args.cron_expr
         );
 
         let frequency = TaskFrequency::from_str(&args.frequency)
             .map_err(|e| CreateScheduledTaskError(e.to_string()))?;
 
+        let misfire_policy = args
+            .misfire_policy
+            .as_deref()
+            .map(MisfirePolicy::from_str)
+            .transpose()
+            .map_err(|e| CreateScheduledTaskError(e.to_string()))?
+            .unwrap_or(MisfirePolicy::RunImmediately);
+
+        let calendar = args
+            .calendar
+            .as_deref()
+            .map(TaskCalendar::from_str)
+            .transpose()
+            .map_err(|e| CreateScheduledTaskError(e.to_string()))?;
+
+        let target_user_id = args.target_user_id.unwrap_or(self.user_id);
+        let timezone = self.manager.timezone_for(target_user_id).await;
+        let end_at = args
+            .end_at
+            .as_deref()
+            .map(|end_at| {
+                parse_local_datetime(end_at, timezone)
+                    .map(|end_at| format_db_datetime(&end_at.with_timezone(&Utc)))
+                    .ok_or_else(|| {
+                        CreateScheduledTaskError(format!("无法解析截止时间 '{}'", end_at))
+                    })
+            })
+            .transpose()?;
+
         let req = CreateTaskRequest {
-            target_user_id: args.user_id,
+            target_user_id,
             frequency,
             cron_expr: args.cron_expr.clone(),
             content: args.content.clone(),
+            context: args.context,
             created_by: TaskCreator::User,
+            creator_user_id: Some(self.user_id),
+            start_at: None,
+            end_at,
+            max_runs: args.max_runs,
+            misfire_policy,
+            max_retries: args.max_retries,
+            urgent: args.urgent.unwrap_or(false),
+            calendar: calendar.unwrap_or(TaskCalendar::Any),
         };
 
         let task = self
@@ -101,10 +182,20 @@ impl Tool for CreateScheduledTask {
             task.id
         );
 
+        let next_run_at = task
+            .next_run_at
+            .as_deref()
+            .and_then(|t| format_local_datetime(t, timezone));
+
         Ok(CreateScheduledTaskResult {
             success: true,
             task_id: task.id,
-            message: format!("已创建定时任务，频率：{:?}", frequency),
+            next_run_at: next_run_at.clone(),
+            message: format!(
+                "已创建定时任务，频率：{:?}，下次触发时间：{}",
+                frequency,
+                next_run_at.as_deref().unwrap_or("无")
+            ),
         })
     }
 }
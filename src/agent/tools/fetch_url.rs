@@ -0,0 +1,511 @@
+use anyhow::Context;
+use encoding_rs::{Encoding, GB18030, UTF_8};
+use reqwest::Url;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::error::Error;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tracing::debug;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_REDIRECTS: usize = 5;
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+const DEFAULT_MAX_CHARS: usize = 3000;
+const MAX_CHARS_LIMIT: usize = 8000;
+
+const REMOVED_BLOCKS: &[&str] = &[
+    "script", "style", "noscript", "svg", "iframe", "template", "head", "nav", "header", "footer",
+    "aside", "form",
+];
+
+const BLOCK_TAGS: &[&str] = &[
+    "p",
+    "div",
+    "br",
+    "li",
+    "tr",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "section",
+    "article",
+    "blockquote",
+    "pre",
+    "table",
+    "ul",
+    "ol",
+];
+
+#[derive(Deserialize)]
+pub struct FetchUrlArgs {
+    pub url: String,
+    pub max_chars: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct FetchUrlResult {
+    pub url: String,
+    pub title: String,
+    pub content: String,
+    pub truncated: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Fetch url error: {0}")]
+pub struct FetchUrlError(String);
+
+/// 只返回公网地址的 DNS 解析器，防止模型通过域名访问内网服务
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect();
+
+            if addrs.is_empty() {
+                return Err(format!("{} 没有可访问的公网地址", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        || a >= 240
+        // 100.64.0.0/10 运营商级 NAT
+        || (a == 100 && (64..128).contains(&b))
+        // 192.0.0.0/24 协议分配
+        || (a == 192 && b == 0 && c == 0)
+        // 198.18.0.0/15 基准测试
+        || (a == 198 && (18..20).contains(&b)))
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    if let Some(ipv4) = ip.to_ipv4_mapped() {
+        return is_public_ipv4(ipv4);
+    }
+
+    let segments = ip.segments();
+    let embedded_ipv4 =
+        |high: u16, low: u16| Ipv4Addr::from((u32::from(high) << 16) | u32::from(low));
+
+    // 64:ff9b::/96 NAT64，最后 32 位是被转换的 IPv4 地址
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        return is_public_ipv4(embedded_ipv4(segments[6], segments[7]));
+    }
+    // 2002::/16 6to4，紧随前缀的 32 位是隧道端点的 IPv4 地址
+    if segments[0] == 0x2002 {
+        return is_public_ipv4(embedded_ipv4(segments[1], segments[2]));
+    }
+
+    let first = segments[0];
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // fc00::/7 唯一本地地址
+        || first & 0xfe00 == 0xfc00
+        // fe80::/10 链路本地地址
+        || first & 0xffc0 == 0xfe80
+        // 2001:db8::/32 文档示例地址
+        || (first == 0x2001 && segments[1] == 0x0db8))
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => is_public_ipv6(ip),
+    }
+}
+
+/// 检查链接的协议和主机，主机是 IP 时不会经过 DNS 解析，需要在这里拦截内网地址
+fn check_url(url: &Url) -> Result<(), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("仅支持 http/https 链接: {}", url));
+    }
+
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("链接缺少主机: {}", url))?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = host.parse::<IpAddr>()
+        && !is_public_ip(ip)
+    {
+        return Err(format!("不允许访问内网地址: {}", url));
+    }
+
+    Ok(())
+}
+
+/// 被拦截的地址只出现在底层错误里，需要把错误链拼起来模型才能看到原因
+fn error_chain(error: &dyn Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(e) = source {
+        message.push_str(&format!(": {}", e));
+        source = e.source();
+    }
+    message
+}
+
+#[derive(Clone)]
+pub struct FetchUrl {
+    client: reqwest::Client,
+}
+
+impl FetchUrl {
+    pub fn new() -> anyhow::Result<Self> {
+        // 重定向后的地址同样要检查，域名统一经过 PublicResolver 解析；
+        // 不使用系统代理，否则解析的是代理的地址而不是目标地址
+        let redirect_policy = redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("重定向次数过多");
+            }
+            match check_url(attempt.url()) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e),
+            }
+        });
+
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .redirect(redirect_policy)
+            .dns_resolver(PublicResolver)
+            .no_proxy()
+            .build()
+            .context("创建网页抓取客户端失败")?;
+
+        Ok(Self { client })
+    }
+
+    async fn fetch(&self, url: &str) -> Result<(String, Vec<u8>, Option<String>), FetchUrlError> {
+        let url =
+            Url::parse(url).map_err(|e| FetchUrlError(format!("无效的链接 {}: {}", url, e)))?;
+        check_url(&url).map_err(FetchUrlError)?;
+
+        let mut response = self
+            .client
+            .get(url)
+            .header(
+                "User-Agent",
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36",
+            )
+            .send()
+            .await
+            .map_err(|e| FetchUrlError(format!("请求失败: {}", error_chain(&e))))?;
+
+        if !response.status().is_success() {
+            return Err(FetchUrlError(format!(
+                "请求失败，状态码: {}",
+                response.status()
+            )));
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_ascii_lowercase());
+
+        if let Some(content_type) = &content_type
+            && !content_type.starts_with("text/")
+            && !content_type.contains("xhtml")
+        {
+            return Err(FetchUrlError(format!("不支持的内容类型: {}", content_type)));
+        }
+
+        let final_url = response.url().to_string();
+        let mut body = Vec::new();
+
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| FetchUrlError(format!("读取响应失败: {}", e)))?
+        {
+            let remaining = MAX_BODY_BYTES - body.len();
+            if chunk.len() >= remaining {
+                body.extend_from_slice(&chunk[..remaining]);
+                debug!("页面超过大小限制，已截断: url={}", final_url);
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok((final_url, body, content_type))
+    }
+
+    fn decode(body: &[u8], content_type: Option<&str>) -> String {
+        let encoding = content_type
+            .and_then(Self::charset_from_content_type)
+            .or_else(|| Self::charset_from_meta(body))
+            .or_else(|| Encoding::for_bom(body).map(|(encoding, _)| encoding));
+
+        let encoding = match encoding {
+            Some(encoding) => encoding,
+            None if std::str::from_utf8(body).is_ok() => UTF_8,
+            None => GB18030,
+        };
+
+        let (text, _, _) = encoding.decode(body);
+        text.into_owned()
+    }
+
+    fn charset_from_content_type(content_type: &str) -> Option<&'static Encoding> {
+        let charset = content_type.split("charset=").nth(1)?;
+        let charset = charset.split(';').next()?.trim().trim_matches('"');
+        Encoding::for_label(charset.as_bytes())
+    }
+
+    fn charset_from_meta(body: &[u8]) -> Option<&'static Encoding> {
+        let head = &body[..body.len().min(4096)];
+        let head = String::from_utf8_lossy(head).to_ascii_lowercase();
+
+        let start = head.find("charset=")? + "charset=".len();
+        let charset: String = head[start..]
+            .trim_start_matches(['"', '\''])
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+
+        Encoding::for_label(charset.as_bytes())
+    }
+
+    fn extract_title(html: &str) -> String {
+        let lower = html.to_ascii_lowercase();
+
+        lower
+            .find("<title")
+            .and_then(|start| Some(start + lower[start..].find('>')? + 1))
+            .and_then(|start| Some((start, start + lower[start..].find("</title>")?)))
+            .map(|(start, end)| Self::decode_entities(html[start..end].trim()))
+            .unwrap_or_default()
+    }
+
+    fn extract_readable_text(html: &str) -> String {
+        let mut html = html.to_string();
+        for tag in REMOVED_BLOCKS {
+            html = Self::remove_blocks(&html, tag);
+        }
+
+        let main = Self::inner_of(&html, "article")
+            .or_else(|| Self::inner_of(&html, "main"))
+            .or_else(|| Self::inner_of(&html, "body"))
+            .unwrap_or(&html);
+
+        let text = Self::strip_tags(main);
+
+        text.lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn remove_blocks(html: &str, tag: &str) -> String {
+        let lower = html.to_ascii_lowercase();
+        let open = format!("<{}", tag);
+        let close = format!("</{}>", tag);
+
+        let mut result = String::with_capacity(html.len());
+        let mut cursor = 0;
+
+        while let Some(offset) = lower[cursor..].find(&open) {
+            let start = cursor + offset;
+            let after = lower[start + open.len()..].chars().next();
+
+            if !matches!(after, Some(c) if c == '>' || c == '/' || c.is_ascii_whitespace()) {
+                result.push_str(&html[cursor..start + open.len()]);
+                cursor = start + open.len();
+                continue;
+            }
+
+            result.push_str(&html[cursor..start]);
+            cursor = match lower[start..].find(&close) {
+                Some(end) => start + end + close.len(),
+                None => html.len(),
+            };
+        }
+
+        result.push_str(&html[cursor..]);
+        result
+    }
+
+    fn inner_of<'a>(html: &'a str, tag: &str) -> Option<&'a str> {
+        let lower = html.to_ascii_lowercase();
+        let start = lower.find(&format!("<{}", tag))?;
+        let start = start + lower[start..].find('>')? + 1;
+        let end = lower.rfind(&format!("</{}>", tag)).unwrap_or(html.len());
+
+        (end > start).then(|| &html[start..end])
+    }
+
+    fn strip_tags(html: &str) -> String {
+        let mut text = String::with_capacity(html.len() / 2);
+        let mut rest = html;
+
+        while let Some(start) = rest.find('<') {
+            text.push_str(&rest[..start]);
+
+            let Some(end) = rest[start..].find('>') else {
+                rest = "";
+                break;
+            };
+
+            let tag = rest[start + 1..start + end]
+                .trim_start_matches('/')
+                .split(|c: char| c.is_ascii_whitespace() || c == '/')
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+
+            if BLOCK_TAGS.contains(&tag.as_str()) {
+                text.push('\n');
+            }
+
+            rest = &rest[start + end + 1..];
+        }
+        text.push_str(rest);
+
+        Self::decode_entities(&text)
+    }
+
+    fn decode_entities(text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = rest.find('&') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let decoded = rest[1..]
+                .find(';')
+                .filter(|end| *end <= 10)
+                .and_then(|end| {
+                    let entity = &rest[1..end + 1];
+                    let ch = match entity {
+                        "amp" => Some('&'),
+                        "lt" => Some('<'),
+                        "gt" => Some('>'),
+                        "quot" => Some('"'),
+                        "apos" => Some('\''),
+                        "nbsp" => Some(' '),
+                        _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                            u32::from_str_radix(&entity[2..], 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                        }
+                        _ if entity.starts_with('#') => {
+                            entity[1..].parse().ok().and_then(char::from_u32)
+                        }
+                        _ => None,
+                    };
+                    ch.map(|ch| (ch, end + 2))
+                });
+
+            match decoded {
+                Some((ch, len)) => {
+                    result.push(ch);
+                    rest = &rest[len..];
+                }
+                None => {
+                    result.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        result.push_str(rest);
+
+        result
+    }
+
+    fn truncate(text: &str, max_chars: usize) -> (String, bool) {
+        match text.char_indices().nth(max_chars) {
+            Some((idx, _)) => (format!("{}……", &text[..idx]), true),
+            None => (text.to_string(), false),
+        }
+    }
+}
+
+impl Tool for FetchUrl {
+    const NAME: &'static str = "fetch_url";
+    type Error = FetchUrlError;
+    type Args = FetchUrlArgs;
+    type Output = FetchUrlResult;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "抓取网页并提取正文文本。当搜索结果的摘要不足以回答问题、需要阅读网页具体内容时使用此工具。返回的内容可能被截断，回答时请附上来源链接。".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "要抓取的网页链接，必须以 http:// 或 https:// 开头"
+                    },
+                    "max_chars": {
+                        "type": "integer",
+                        "description": format!("返回正文的最大字符数，默认 {}，最大 {}", DEFAULT_MAX_CHARS, MAX_CHARS_LIMIT)
+                    }
+                },
+                "required": ["url"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        debug!("[Tool] fetch_url called: url={}", args.url);
+
+        let (url, body, content_type) = self.fetch(&args.url).await?;
+        let html = Self::decode(&body, content_type.as_deref());
+
+        let title = Self::extract_title(&html);
+        let text = Self::extract_readable_text(&html);
+
+        if text.is_empty() {
+            return Err(FetchUrlError("未能从页面中提取到正文".to_string()));
+        }
+
+        let max_chars = args
+            .max_chars
+            .unwrap_or(DEFAULT_MAX_CHARS)
+            .min(MAX_CHARS_LIMIT);
+        let (content, truncated) = Self::truncate(&text, max_chars);
+
+        debug!(
+            "[Tool] fetch_url completed: url={}, chars={}, truncated={}",
+            url,
+            content.chars().count(),
+            truncated
+        );
+
+        Ok(FetchUrlResult {
+            url,
+            title,
+            content,
+            truncated,
+        })
+    }
+}
@@ -1,4 +1,6 @@
-use chrono::Local;
+use crate::utils::parse_timezone;
+use chrono::Utc;
+use chrono_tz::Tz;
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
@@ -6,19 +8,31 @@ use serde_json::json;
 use tracing::debug;
 
 #[derive(Deserialize)]
-pub struct GetCurrentTimeArgs {}
+pub struct GetCurrentTimeArgs {
+    pub timezone: Option<String>,
+}
 
 #[derive(Serialize)]
 pub struct TimeResult {
     current_time: String,
+    weekday: String,
+    timezone: String,
     timestamp: i64,
 }
 
 #[derive(Debug, thiserror::Error)]
-#[error("Time error")]
-pub struct TimeError;
+#[error("Time error: {0}")]
+pub struct TimeError(String);
+
+pub struct GetCurrentTime {
+    default_timezone: Tz,
+}
 
-pub struct GetCurrentTime;
+impl GetCurrentTime {
+    pub fn new(default_timezone: Tz) -> Self {
+        Self { default_timezone }
+    }
+}
 
 impl Tool for GetCurrentTime {
     const NAME: &'static str = "get_current_time";
@@ -29,22 +43,43 @@ impl Tool for GetCurrentTime {
     async fn definition(&self, _prompt: String) -> ToolDefinition {
         ToolDefinition {
             name: Self::NAME.to_string(),
-            description: "获取当前时间，返回格式化的时间字符串和时间戳。当用户询问现在几点、今天日期、当前时间等问题时使用此工具。".to_string(),
+            description: "获取当前时间，返回格式化的时间字符串和时间戳。当用户询问现在几点、今天日期、当前时间等问题时使用此工具。如果知道用户所在时区，请传入 timezone 参数。".to_string(),
             parameters: json!({
                 "type": "object",
-                "properties": {}
+                "properties": {
+                    "timezone": {
+                        "type": "string",
+                        "description": format!("IANA 时区名，例如 Asia/Shanghai、America/New_York，不传则使用默认时区 {}", self.default_timezone)
+                    }
+                }
             }),
         }
     }
 
-    async fn call(&self, _args: Self::Args) -> Result<Self::Output, Self::Error> {
-        debug!("[Tool] get_current_time called");
-        let now = Local::now();
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        debug!(
+            "[Tool] get_current_time called: timezone={:?}",
+            args.timezone
+        );
+
+        let timezone = match args.timezone.as_deref() {
+            Some(tz) if !tz.trim().is_empty() => {
+                parse_timezone(tz).map_err(|e| TimeError(e.to_string()))?
+            }
+            _ => self.default_timezone,
+        };
+
+        let now = Utc::now().with_timezone(&timezone);
         let result = TimeResult {
             current_time: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+            weekday: now.format("%A").to_string(),
+            timezone: timezone.name().to_string(),
             timestamp: now.timestamp(),
         };
-        debug!("[Tool] get_current_time completed: {}", result.current_time);
+        debug!(
+            "[Tool] get_current_time completed: {} {}",
+            result.current_time, result.timezone
+        );
         Ok(result)
     }
 }
@@ -0,0 +1,120 @@
+use crate::scheduler::SchedulerManager;
+use crate::utils::format_local_datetime;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::debug;
+
+#[derive(Deserialize)]
+pub struct ListScheduledTasksArgs {}
+
+#[derive(Serialize)]
+pub struct ListScheduledTasksResult {
+    pub timezone: String,
+    pub tasks: Vec<ScheduledTaskItem>,
+}
+
+#[derive(Serialize)]
+pub struct ScheduledTaskItem {
+    pub task_id: i64,
+    pub target_user_id: i64,
+    pub creator_user_id: Option<i64>,
+    pub frequency: String,
+    pub cron_expr: String,
+    pub calendar: String,
+    pub content: String,
+    pub context: Option<String>,
+    pub enabled: bool,
+    pub end_at: Option<String>,
+    pub max_runs: Option<i64>,
+    pub run_count: i64,
+    pub last_run_at: Option<String>,
+    pub next_run_at: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("List scheduled tasks error: {0}")]
+pub struct ListScheduledTasksError(String);
+
+pub struct ListScheduledTasks {
+    manager: Arc<SchedulerManager>,
+    user_id: i64,
+}
+
+impl ListScheduledTasks {
+    pub fn new(manager: Arc<SchedulerManager>, user_id: i64) -> Self {
+        Self { manager, user_id }
+    }
+}
+
+impl Tool for ListScheduledTasks {
+    const NAME: &'static str = "list_scheduled_tasks";
+    type Error = ListScheduledTasksError;
+    type Args = ListScheduledTasksArgs;
+    type Output = ListScheduledTasksResult;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "查询用户创建的所有定时任务/日程提醒，包括已暂停的任务。返回的时间均已换算为用户所在时区，next_run_at 为下次触发时间。在取消、暂停、恢复或修改任务前，先用此工具找到对应的任务ID。"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        }
+    }
+
+    async fn call(&self, _args: Self::Args) -> Result<Self::Output, Self::Error> {
+        debug!(
+            "[Tool] list_scheduled_tasks called: user_id={}",
+            self.user_id
+        );
+
+        let tasks = self
+            .manager
+            .list_tasks(self.user_id)
+            .await
+            .map_err(|e| ListScheduledTasksError(e.to_string()))?;
+
+        let timezone = self.manager.timezone_for(self.user_id).await;
+        let tasks: Vec<ScheduledTaskItem> = tasks
+            .into_iter()
+            .map(|task| ScheduledTaskItem {
+                task_id: task.id,
+                target_user_id: task.target_user_id,
+                creator_user_id: task.creator_user_id,
+                frequency: task.frequency.as_str().to_string(),
+                cron_expr: task.cron_expr,
+                calendar: task.calendar.as_str().to_string(),
+                content: task.content,
+                context: task.context,
+                enabled: task.enabled,
+                end_at: task
+                    .end_at
+                    .and_then(|t| format_local_datetime(&t, timezone)),
+                max_runs: task.max_runs,
+                run_count: task.run_count,
+                last_run_at: task
+                    .last_run_at
+                    .and_then(|t| format_local_datetime(&t, timezone)),
+                next_run_at: task
+                    .next_run_at
+                    .and_then(|t| format_local_datetime(&t, timezone)),
+            })
+            .collect();
+
+        debug!(
+            "[Tool] list_scheduled_tasks completed: found {} tasks",
+            tasks.len()
+        );
+
+        Ok(ListScheduledTasksResult {
+            timezone: timezone.name().to_string(),
+            tasks,
+        })
+    }
+}
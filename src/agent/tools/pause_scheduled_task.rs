@@ -0,0 +1,80 @@
+use crate::scheduler::SchedulerManager;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::debug;
+
+#[derive(Deserialize)]
+pub struct PauseScheduledTaskArgs {
+    pub task_id: i64,
+}
+
+#[derive(Serialize)]
+pub struct PauseScheduledTaskResult {
+    pub success: bool,
+    pub task_id: i64,
+    pub message: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Pause scheduled task error: {0}")]
+pub struct PauseScheduledTaskError(String);
+
+pub struct PauseScheduledTask {
+    manager: Arc<SchedulerManager>,
+    user_id: i64,
+}
+
+impl PauseScheduledTask {
+    pub fn new(manager: Arc<SchedulerManager>, user_id: i64) -> Self {
+        Self { manager, user_id }
+    }
+}
+
+impl Tool for PauseScheduledTask {
+    const NAME: &'static str = "pause_scheduled_task";
+    type Error = PauseScheduledTaskError;
+    type Args = PauseScheduledTaskArgs;
+    type Output = PauseScheduledTaskResult;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "暂停用户的一个定时任务，暂停后任务保留但不会触发，之后可以用 resume_scheduled_task 恢复。"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "task_id": {
+                        "type": "integer",
+                        "description": "要暂停的任务ID"
+                    }
+                },
+                "required": ["task_id"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        debug!(
+            "[Tool] pause_scheduled_task called: user_id={}, task_id={}",
+            self.user_id, args.task_id
+        );
+
+        let task = self
+            .manager
+            .pause_task(self.user_id, args.task_id)
+            .await
+            .map_err(|e| PauseScheduledTaskError(e.to_string()))?;
+
+        debug!("[Tool] pause_scheduled_task completed: task_id={}", task.id);
+
+        Ok(PauseScheduledTaskResult {
+            success: true,
+            task_id: task.id,
+            message: format!("已暂停定时任务：{}", task.content),
+        })
+    }
+}
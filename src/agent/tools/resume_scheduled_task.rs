@@ -0,0 +1,83 @@
+use crate::scheduler::SchedulerManager;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::debug;
+
+#[derive(Deserialize)]
+pub struct ResumeScheduledTaskArgs {
+    pub task_id: i64,
+}
+
+#[derive(Serialize)]
+pub struct ResumeScheduledTaskResult {
+    pub success: bool,
+    pub task_id: i64,
+    pub message: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Resume scheduled task error: {0}")]
+pub struct ResumeScheduledTaskError(String);
+
+pub struct ResumeScheduledTask {
+    manager: Arc<SchedulerManager>,
+    user_id: i64,
+}
+
+impl ResumeScheduledTask {
+    pub fn new(manager: Arc<SchedulerManager>, user_id: i64) -> Self {
+        Self { manager, user_id }
+    }
+}
+
+impl Tool for ResumeScheduledTask {
+    const NAME: &'static str = "resume_scheduled_task";
+    type Error = ResumeScheduledTaskError;
+    type Args = ResumeScheduledTaskArgs;
+    type Output = ResumeScheduledTaskResult;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "恢复用户一个已暂停的定时任务，恢复后任务会按原来的时间继续触发。"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "task_id": {
+                        "type": "integer",
+                        "description": "要恢复的任务ID"
+                    }
+                },
+                "required": ["task_id"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        debug!(
+            "[Tool] resume_scheduled_task called: user_id={}, task_id={}",
+            self.user_id, args.task_id
+        );
+
+        let task = self
+            .manager
+            .resume_task(self.user_id, args.task_id)
+            .await
+            .map_err(|e| ResumeScheduledTaskError(e.to_string()))?;
+
+        debug!(
+            "[Tool] resume_scheduled_task completed: task_id={}",
+            task.id
+        );
+
+        Ok(ResumeScheduledTaskResult {
+            success: true,
+            task_id: task.id,
+            message: format!("已恢复定时任务：{}", task.content),
+        })
+    }
+}
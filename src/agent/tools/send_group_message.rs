@@ -0,0 +1,128 @@
+use milky_rust_sdk::MilkyClient;
+use milky_rust_sdk::prelude::{MentionData, OutgoingSegment, TextData};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::debug;
+
+#[derive(Deserialize)]
+pub struct SendGroupMessageArgs {
+    pub messages: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct SendGroupMessageResult {
+    pub success: bool,
+    pub sent_count: usize,
+    pub message_seqs: Vec<i64>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Send group message error: {0}")]
+pub struct SendGroupMessageError(String);
+
+/// 绑定到触发消息所在的群和发送者，模型只能回复这条消息，不能向其他群发言
+pub struct SendGroupMessage {
+    client: Arc<MilkyClient>,
+    group_id: i64,
+    user_id: i64,
+}
+
+impl SendGroupMessage {
+    pub fn new(client: Arc<MilkyClient>, group_id: i64, user_id: i64) -> Self {
+        Self {
+            client,
+            group_id,
+            user_id,
+        }
+    }
+}
+
+impl Tool for SendGroupMessage {
+    const NAME: &'static str = "send_group_message";
+    type Error = SendGroupMessageError;
+    type Args = SendGroupMessageArgs;
+    type Output = SendGroupMessageResult;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "在群聊中回复发消息的用户，消息会发送到消息所在的群，第一条消息会自动@该用户。messages参数是一个字符串数组，每个字符串会作为单独的一条消息发送。\n\n重要规则：每次互动只能调用一次此工具".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "messages": {
+                        "type": "array",
+                        "items": {
+                            "type": "string"
+                        },
+                        "description": "要发送的消息列表，每条消息会单独发送"
+                    }
+                },
+                "required": ["messages"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        debug!(
+            "[Tool] send_group_message called: group_id={}, user_id={}, message_count={}",
+            self.group_id,
+            self.user_id,
+            args.messages.len()
+        );
+
+        let mut sent_count = 0;
+        let mut message_seqs = Vec::new();
+
+        for (index, msg) in args.messages.into_iter().enumerate() {
+            let segments = if index == 0 {
+                vec![
+                    OutgoingSegment::Mention(MentionData {
+                        user_id: self.user_id,
+                    }),
+                    OutgoingSegment::Text(TextData {
+                        text: format!(" {}", msg),
+                    }),
+                ]
+            } else {
+                vec![OutgoingSegment::Text(TextData { text: msg })]
+            };
+
+            match self
+                .client
+                .send_group_message(self.group_id, segments)
+                .await
+            {
+                Ok(response) => {
+                    sent_count += 1;
+                    message_seqs.push(response.message_seq);
+                }
+                Err(e) => {
+                    debug!(
+                        "[Tool] send_group_message failed at message {}: {}",
+                        sent_count + 1,
+                        e
+                    );
+                    return Err(SendGroupMessageError(format!(
+                        "发送第{}条消息失败: {}",
+                        sent_count + 1,
+                        e
+                    )));
+                }
+            }
+        }
+
+        debug!(
+            "[Tool] send_group_message completed: sent_count={}",
+            sent_count
+        );
+        Ok(SendGroupMessageResult {
+            success: true,
+            sent_count,
+            message_seqs,
+        })
+    }
+}
@@ -17,6 +17,7 @@ pub struct SendMessageArgs {
 pub struct SendMessageResult {
     pub success: bool,
     pub sent_count: usize,
+    pub message_seqs: Vec<i64>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -25,11 +26,23 @@ pub struct SendMessageError(String);
 
 pub struct SendMessage {
     client: Arc<MilkyClient>,
+    /// 设置后只能发给该用户，用于陌生人等受限的对话
+    recipient: Option<i64>,
 }
 
 impl SendMessage {
     pub fn new(client: Arc<MilkyClient>) -> Self {
-        Self { client }
+        Self {
+            client,
+            recipient: None,
+        }
+    }
+
+    pub fn to_user(client: Arc<MilkyClient>, user_id: i64) -> Self {
+        Self {
+            client,
+            recipient: Some(user_id),
+        }
     }
 }
 
@@ -69,7 +82,18 @@ impl Tool for SendMessage {
             args.user_id,
             args.messages.len()
         );
+
+        if let Some(recipient) = self.recipient
+            && recipient != args.user_id
+        {
+            return Err(SendMessageError(format!(
+                "只能给当前对话的用户 {} 发送消息",
+                recipient
+            )));
+        }
+
         let mut sent_count = 0;
+        let mut message_seqs = Vec::new();
 
         for msg in args.messages {
             let segments = vec![OutgoingSegment::Text(TextData { text: msg })];
@@ -79,7 +103,10 @@ impl Tool for SendMessage {
                 .send_private_message(args.user_id, segments)
                 .await
             {
-                Ok(_) => sent_count += 1,
+                Ok(response) => {
+                    sent_count += 1;
+                    message_seqs.push(response.message_seq);
+                }
                 Err(e) => {
                     debug!(
                         "[Tool] send_message failed at message {}: {}",
@@ -99,6 +126,7 @@ impl Tool for SendMessage {
         Ok(SendMessageResult {
             success: true,
             sent_count,
+            message_seqs,
         })
     }
 }
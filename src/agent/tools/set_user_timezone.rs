@@ -0,0 +1,79 @@
+use crate::scheduler::SchedulerManager;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::debug;
+
+#[derive(Deserialize)]
+pub struct SetUserTimezoneArgs {
+    pub timezone: String,
+}
+
+#[derive(Serialize)]
+pub struct SetUserTimezoneResult {
+    pub success: bool,
+    pub timezone: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Set user timezone error: {0}")]
+pub struct SetUserTimezoneError(String);
+
+pub struct SetUserTimezone {
+    manager: Arc<SchedulerManager>,
+    user_id: i64,
+}
+
+impl SetUserTimezone {
+    pub fn new(manager: Arc<SchedulerManager>, user_id: i64) -> Self {
+        Self { manager, user_id }
+    }
+}
+
+impl Tool for SetUserTimezone {
+    const NAME: &'static str = "set_user_timezone";
+    type Error = SetUserTimezoneError;
+    type Args = SetUserTimezoneArgs;
+    type Output = SetUserTimezoneResult;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "记住用户所在的时区。当用户提到自己在哪个城市/国家生活，或明确告知时区时使用，之后的定时任务都会按该时区触发。"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "timezone": {
+                        "type": "string",
+                        "description": "IANA 时区名，例如 Asia/Shanghai、Europe/London、America/Los_Angeles"
+                    }
+                },
+                "required": ["timezone"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        debug!(
+            "[Tool] set_user_timezone called: user_id={}, timezone={}",
+            self.user_id, args.timezone
+        );
+
+        let user = self
+            .manager
+            .set_user_timezone(self.user_id, &args.timezone)
+            .await
+            .map_err(|e| SetUserTimezoneError(e.to_string()))?;
+
+        let timezone = user.timezone.unwrap_or_default();
+        debug!("[Tool] set_user_timezone completed: timezone={}", timezone);
+
+        Ok(SetUserTimezoneResult {
+            success: true,
+            timezone,
+        })
+    }
+}
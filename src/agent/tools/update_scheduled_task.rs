@@ -0,0 +1,245 @@
+use crate::db::scheduler_model::{MisfirePolicy, TaskCalendar, TaskFrequency, UpdateTaskRequest};
+use crate::scheduler::SchedulerManager;
+use crate::utils::{format_db_datetime, format_local_datetime, parse_local_datetime};
+use chrono::Utc;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::debug;
+
+#[derive(Deserialize)]
+pub struct UpdateScheduledTaskArgs {
+    pub task_id: i64,
+    pub content: Option<String>,
+    pub context: Option<String>,
+    pub cron_expr: Option<String>,
+    pub frequency: Option<String>,
+    pub end_at: Option<String>,
+    pub max_runs: Option<i64>,
+    pub misfire_policy: Option<String>,
+    pub max_retries: Option<i64>,
+    pub urgent: Option<bool>,
+    pub calendar: Option<String>,
+    #[serde(default)]
+    pub clear: Vec<String>,
+}
+
+/// 可以通过 clear 清除的字段
+const CLEARABLE_FIELDS: &[&str] = &["context", "end_at", "max_runs", "max_retries"];
+
+/// 把参数转换为可清除字段的修改：None 不修改，Some(None) 清除
+fn field_update<T>(
+    clear: &[String],
+    name: &str,
+    value: Option<T>,
+) -> Result<Option<Option<T>>, UpdateScheduledTaskError> {
+    let cleared = clear.iter().any(|field| field == name);
+    match value {
+        Some(_) if cleared => Err(UpdateScheduledTaskError(format!(
+            "字段 {} 不能同时修改和清除",
+            name
+        ))),
+        Some(value) => Ok(Some(Some(value))),
+        None if cleared => Ok(Some(None)),
+        None => Ok(None),
+    }
+}
+
+#[derive(Serialize)]
+pub struct UpdateScheduledTaskResult {
+    pub success: bool,
+    pub task_id: i64,
+    pub next_run_at: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Update scheduled task error: {0}")]
+pub struct UpdateScheduledTaskError(String);
+
+pub struct UpdateScheduledTask {
+    manager: Arc<SchedulerManager>,
+    user_id: i64,
+}
+
+impl UpdateScheduledTask {
+    pub fn new(manager: Arc<SchedulerManager>, user_id: i64) -> Self {
+        Self { manager, user_id }
+    }
+}
+
+impl Tool for UpdateScheduledTask {
+    const NAME: &'static str = "update_scheduled_task";
+    type Error = UpdateScheduledTaskError;
+    type Args = UpdateScheduledTaskArgs;
+    type Output = UpdateScheduledTaskResult;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "修改用户已有的定时任务，可以修改提醒内容、触发时间、频率、截止时间或执行次数，未提供的字段保持不变，需要去掉截止时间、执行次数等设置时使用 clear。修改成功后需要告知用户。"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "task_id": {
+                        "type": "integer",
+                        "description": "要修改的任务ID"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "新的提醒内容prompt"
+                    },
+                    "context": {
+                        "type": "string",
+                        "description": "新的对话背景摘要，提醒触发时会一并提供"
+                    },
+                    "cron_expr": {
+                        "type": "string",
+                        "description": "新的Cron表达式，格式为：秒 分 时 日 月 星期"
+                    },
+                    "frequency": {
+                        "type": "string",
+                        "enum": ["once", "daily", "weekly", "monthly", "weekdays", "cron"],
+                        "description": "新的任务频率，需要和Cron表达式的日、星期字段一致"
+                    },
+                    "end_at": {
+                        "type": "string",
+                        "description": "新的截止时间，ISO 格式，按用户所在时区解释"
+                    },
+                    "max_runs": {
+                        "type": "integer",
+                        "description": "新的最多执行次数"
+                    },
+                    "misfire_policy": {
+                        "type": "string",
+                        "enum": ["run_immediately", "skip", "expire"],
+                        "description": "新的错过触发处理方式"
+                    },
+                    "max_retries": {
+                        "type": "integer",
+                        "description": "可选，任务执行失败后自动重试的次数，不提供则使用默认值"
+                    },
+                    "urgent": {
+                        "type": "boolean",
+                        "description": "可选，是否为紧急提醒，紧急提醒在用户的安静时段和免打扰期间也会照常送达，默认 false"
+                    },
+                    "calendar": {
+                        "type": "string",
+                        "enum": ["any", "cn_workday"],
+                        "description": "可选，执行日历：any 按 cron 正常执行，cn_workday 按中国法定工作日执行，跳过节假日并在调休上班的周末照常执行，只适用于每天或工作日重复的任务"
+                    },
+                    "clear": {
+                        "type": "array",
+                        "items": { "type": "string", "enum": CLEARABLE_FIELDS },
+                        "description": "可选，要清除的字段，例如用户说不再需要截止时间时传 [\"end_at\"]；清除的字段不能同时提供新值"
+                    }
+                },
+                "required": ["task_id"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        debug!(
+            "[Tool] update_scheduled_task called: user_id={}, task_id={}",
+            self.user_id, args.task_id
+        );
+
+        let frequency = args
+            .frequency
+            .as_deref()
+            .map(TaskFrequency::from_str)
+            .transpose()
+            .map_err(|e| UpdateScheduledTaskError(e.to_string()))?;
+
+        let misfire_policy = args
+            .misfire_policy
+            .as_deref()
+            .map(MisfirePolicy::from_str)
+            .transpose()
+            .map_err(|e| UpdateScheduledTaskError(e.to_string()))?;
+
+        let calendar = args
+            .calendar
+            .as_deref()
+            .map(TaskCalendar::from_str)
+            .transpose()
+            .map_err(|e| UpdateScheduledTaskError(e.to_string()))?;
+
+        // 截止时间和下次执行时间按任务接收者的时区解释，master 修改别人的任务时也一样
+        let current = self
+            .manager
+            .get_owned_task(self.user_id, args.task_id)
+            .await
+            .map_err(|e| UpdateScheduledTaskError(e.to_string()))?;
+        let timezone = self.manager.timezone_for(current.target_user_id).await;
+        let end_at = args
+            .end_at
+            .as_deref()
+            .map(|end_at| {
+                parse_local_datetime(end_at, timezone)
+                    .map(|end_at| format_db_datetime(&end_at.with_timezone(&Utc)))
+                    .ok_or_else(|| {
+                        UpdateScheduledTaskError(format!("无法解析截止时间 '{}'", end_at))
+                    })
+            })
+            .transpose()?;
+
+        if let Some(field) = args
+            .clear
+            .iter()
+            .find(|field| !CLEARABLE_FIELDS.contains(&field.as_str()))
+        {
+            return Err(UpdateScheduledTaskError(format!(
+                "不支持清除字段 {}，可清除的字段: {}",
+                field,
+                CLEARABLE_FIELDS.join(", ")
+            )));
+        }
+        let clear = &args.clear;
+
+        let req = UpdateTaskRequest {
+            task_id: args.task_id,
+            frequency,
+            cron_expr: args.cron_expr,
+            content: args.content,
+            context: field_update(clear, "context", args.context)?,
+            end_at: field_update(clear, "end_at", end_at)?,
+            max_runs: field_update(clear, "max_runs", args.max_runs)?,
+            misfire_policy,
+            max_retries: field_update(clear, "max_retries", args.max_retries)?,
+            urgent: args.urgent,
+            calendar,
+        };
+
+        let task = self
+            .manager
+            .update_task(self.user_id, req)
+            .await
+            .map_err(|e| UpdateScheduledTaskError(e.to_string()))?;
+
+        debug!(
+            "[Tool] update_scheduled_task completed: task_id={}",
+            task.id
+        );
+
+        let next_run_at = task
+            .next_run_at
+            .as_deref()
+            .and_then(|t| format_local_datetime(t, timezone));
+
+        Ok(UpdateScheduledTaskResult {
+            success: true,
+            task_id: task.id,
+            next_run_at,
+            message: format!(
+                "已更新定时任务，频率：{}，Cron：{}",
+                task.frequency.as_str(),
+                task.cron_expr
+            ),
+        })
+    }
+}
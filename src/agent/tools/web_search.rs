@@ -1,8 +1,26 @@
+mod bing;
+mod brave;
+mod cache;
+mod duckduckgo;
+mod provider;
+mod searxng;
+
+use crate::config::{SearchConfig, SearchProviderKind};
+use crate::db::search_cache_service::SearchCacheService;
+use anyhow::Context;
+use bing::Bing;
+use brave::Brave;
+use cache::SearchCache;
+use duckduckgo::DuckDuckGo;
+use provider::SearchProvider;
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
+use searxng::Searxng;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tracing::debug;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
 
 #[derive(Deserialize)]
 pub struct WebSearchArgs {
@@ -12,10 +30,11 @@ pub struct WebSearchArgs {
 #[derive(Serialize)]
 pub struct WebSearchResult {
     pub query: String,
+    pub provider: String,
     pub results: Vec<SearchResultItem>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResultItem {
     pub title: String,
     pub url: String,
@@ -26,88 +45,79 @@ pub struct SearchResultItem {
 #[error("Web search error: {0}")]
 pub struct WebSearchError(String);
 
+#[derive(Clone)]
 pub struct WebSearch {
-    client: reqwest::Client,
+    providers: Arc<Vec<Box<dyn SearchProvider>>>,
+    max_results: usize,
+    cache: Arc<SearchCache>,
 }
 
 impl WebSearch {
-    pub fn new() -> Self {
-        Self {
-            client: reqwest::Client::new(),
+    pub fn new(config: &SearchConfig, cache_service: SearchCacheService) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .context("创建搜索客户端失败")?;
+
+        let mut providers: Vec<Box<dyn SearchProvider>> = Vec::new();
+
+        for kind in &config.providers {
+            match kind {
+                SearchProviderKind::DuckDuckGo => {
+                    providers.push(Box::new(DuckDuckGo::new(client.clone())));
+                }
+                SearchProviderKind::Searxng => match &config.searxng_url {
+                    Some(base_url) => {
+                        providers.push(Box::new(Searxng::new(client.clone(), base_url)));
+                    }
+                    None => warn!("未配置 searxng_url，跳过 SearXNG 搜索"),
+                },
+                SearchProviderKind::Bing => match &config.bing_api_key {
+                    Some(api_key) => providers.push(Box::new(Bing::new(client.clone(), api_key))),
+                    None => warn!("未配置 bing_api_key，跳过 Bing 搜索"),
+                },
+                SearchProviderKind::Brave => match &config.brave_api_key {
+                    Some(api_key) => {
+                        providers.push(Box::new(Brave::new(client.clone(), api_key)));
+                    }
+                    None => warn!("未配置 brave_api_key，跳过 Brave 搜索"),
+                },
+            }
         }
-    }
-
-    async fn search_duckduckgo(&self, query: &str) -> Result<Vec<SearchResultItem>, WebSearchError> {
-        let url = format!(
-            "https://html.duckduckgo.com/html/?q={}",
-            urlencoding::encode(query)
-        );
-
-        let response = self
-            .client
-            .get(&url)
-            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-            .send()
-            .await
-            .map_err(|e| WebSearchError(format!("请求失败: {}", e)))?;
 
-        let html = response
-            .text()
-            .await
-            .map_err(|e| WebSearchError(format!("读取响应失败: {}", e)))?;
-
-        let results = Self::parse_duckduckgo_html(&html);
-
-        if results.is_empty() {
-            return Err(WebSearchError("未找到搜索结果".to_string()));
+        if providers.is_empty() {
+            warn!("没有可用的搜索引擎配置，使用 DuckDuckGo");
+            providers.push(Box::new(DuckDuckGo::new(client)));
         }
 
-        Ok(results)
+        Ok(Self {
+            providers: Arc::new(providers),
+            max_results: config.max_results,
+            cache: Arc::new(SearchCache::new(config, cache_service)),
+        })
     }
 
-    fn parse_duckduckgo_html(html: &str) -> Vec<SearchResultItem> {
-        let mut results = Vec::new();
-
-        let result_divs: Vec<&str> = html.split("result__body").collect();
-
-        for (_i, div) in result_divs.iter().enumerate().skip(1).take(5) {
-            if let Some(title) = Self::extract_between(div, "result__a\">", "</a>") {
-                let url = Self::extract_between(div, "uddg=", "\"")
-                    .and_then(|u| urlencoding::decode(&u).ok().map(|s| s.to_string()))
-                    .unwrap_or_default();
-
-                let snippet = Self::extract_between(div, "result__snippet\">", "</a>")
-                    .unwrap_or_default();
-
-                if !title.is_empty() && !url.is_empty() {
-                    results.push(SearchResultItem {
-                        title: Self::clean_html(&title),
-                        url,
-                        snippet: Self::clean_html(&snippet),
-                    });
+    async fn search(
+        &self,
+        query: &str,
+    ) -> Result<(&'static str, Vec<SearchResultItem>), WebSearchError> {
+        let mut errors = Vec::new();
+
+        for provider in self.providers.iter() {
+            match provider.search(query, self.max_results).await {
+                Ok(results) if !results.is_empty() => return Ok((provider.name(), results)),
+                Ok(_) => {
+                    warn!("搜索引擎 {} 未返回结果，尝试下一个", provider.name());
+                    errors.push(format!("{}: 未找到搜索结果", provider.name()));
+                }
+                Err(e) => {
+                    warn!("搜索引擎 {} 搜索失败，尝试下一个: {}", provider.name(), e);
+                    errors.push(format!("{}: {}", provider.name(), e.0));
                 }
             }
         }
 
-        results
-    }
-
-    fn extract_between<'a>(text: &'a str, start: &str, end: &str) -> Option<String> {
-        let start_idx = text.find(start)? + start.len();
-        let remaining = &text[start_idx..];
-        let end_idx = remaining.find(end)?;
-        Some(remaining[..end_idx].to_string())
-    }
-
-    fn clean_html(text: &str) -> String {
-        text.replace("<b>", "")
-            .replace("</b>", "")
-            .replace("&amp;", "&")
-            .replace("&lt;", "<")
-            .replace("&gt;", ">")
-            .replace("&quot;", "\"")
-            .trim()
-            .to_string()
+        Err(WebSearchError(errors.join("; ")))
     }
 }
 
@@ -120,7 +130,7 @@ impl Tool for WebSearch {
     async fn definition(&self, _prompt: String) -> ToolDefinition {
         ToolDefinition {
             name: Self::NAME.to_string(),
-            description: "使用DuckDuckGo搜索引擎在互联网上搜索信息。当用户询问需要实时信息、最新资讯、或你不确定的知识时使用此工具。返回搜索结果包括标题、链接和摘要。".to_string(),
+            description: "使用搜索引擎在互联网上搜索信息。当用户询问需要实时信息、最新资讯、或你不确定的知识时使用此工具。返回搜索结果包括标题、链接和摘要。".to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
@@ -137,13 +147,27 @@ impl Tool for WebSearch {
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
         debug!("[Tool] web_search called: query={}", args.query);
 
-        let results = self.search_duckduckgo(&args.query).await?;
+        let key = SearchCache::normalize(&args.query);
+
+        let (provider, results) = match self.cache.get(&key).await {
+            Some(cached) => cached,
+            None => {
+                let (provider, results) = self.search(&args.query).await?;
+                self.cache.put(&key, provider, &results).await;
+                (provider.to_string(), results)
+            }
+        };
 
-        debug!("[Tool] web_search completed: found {} results", results.len());
+        debug!(
+            "[Tool] web_search completed: provider={}, found {} results",
+            provider,
+            results.len()
+        );
 
         Ok(WebSearchResult {
             query: args.query,
+            provider,
             results,
         })
     }
-}
\ No newline at end of file
+}
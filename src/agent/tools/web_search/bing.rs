@@ -0,0 +1,93 @@
+use super::provider::SearchProvider;
+use super::{SearchResultItem, WebSearchError};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+const BING_ENDPOINT: &str = "https://api.bing.microsoft.com/v7.0/search";
+
+#[derive(Deserialize)]
+struct BingResponse {
+    #[serde(rename = "webPages")]
+    web_pages: Option<BingWebPages>,
+}
+
+#[derive(Deserialize)]
+struct BingWebPages {
+    #[serde(default)]
+    value: Vec<BingResult>,
+}
+
+#[derive(Deserialize)]
+struct BingResult {
+    name: String,
+    url: String,
+    #[serde(default)]
+    snippet: String,
+}
+
+pub struct Bing {
+    client: reqwest::Client,
+    api_key: String,
+}
+
+impl Bing {
+    pub fn new(client: reqwest::Client, api_key: &str) -> Self {
+        Self {
+            client,
+            api_key: api_key.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl SearchProvider for Bing {
+    fn name(&self) -> &'static str {
+        "bing"
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        count: usize,
+    ) -> Result<Vec<SearchResultItem>, WebSearchError> {
+        let url = format!(
+            "{}?q={}&count={}&mkt=zh-CN",
+            BING_ENDPOINT,
+            urlencoding::encode(query),
+            count
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .header("Ocp-Apim-Subscription-Key", &self.api_key)
+            .send()
+            .await
+            .map_err(|e| WebSearchError(format!("请求失败: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(WebSearchError(format!(
+                "请求失败，状态码: {}",
+                response.status()
+            )));
+        }
+
+        let body: BingResponse = response
+            .json()
+            .await
+            .map_err(|e| WebSearchError(format!("解析响应失败: {}", e)))?;
+
+        Ok(body
+            .web_pages
+            .map(|pages| pages.value)
+            .unwrap_or_default()
+            .into_iter()
+            .take(count)
+            .map(|r| SearchResultItem {
+                title: r.name,
+                url: r.url,
+                snippet: r.snippet,
+            })
+            .collect())
+    }
+}
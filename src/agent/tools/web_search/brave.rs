@@ -0,0 +1,93 @@
+use super::provider::{SearchProvider, clean_html};
+use super::{SearchResultItem, WebSearchError};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+const BRAVE_ENDPOINT: &str = "https://api.search.brave.com/res/v1/web/search";
+
+#[derive(Deserialize)]
+struct BraveResponse {
+    web: Option<BraveWeb>,
+}
+
+#[derive(Deserialize)]
+struct BraveWeb {
+    #[serde(default)]
+    results: Vec<BraveResult>,
+}
+
+#[derive(Deserialize)]
+struct BraveResult {
+    title: String,
+    url: String,
+    #[serde(default)]
+    description: String,
+}
+
+pub struct Brave {
+    client: reqwest::Client,
+    api_key: String,
+}
+
+impl Brave {
+    pub fn new(client: reqwest::Client, api_key: &str) -> Self {
+        Self {
+            client,
+            api_key: api_key.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl SearchProvider for Brave {
+    fn name(&self) -> &'static str {
+        "brave"
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        count: usize,
+    ) -> Result<Vec<SearchResultItem>, WebSearchError> {
+        let url = format!(
+            "{}?q={}&count={}",
+            BRAVE_ENDPOINT,
+            urlencoding::encode(query),
+            count
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .header("Accept", "application/json")
+            .header("X-Subscription-Token", &self.api_key)
+            .send()
+            .await
+            .map_err(|e| WebSearchError(format!("请求失败: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(WebSearchError(format!(
+                "请求失败，状态码: {}",
+                response.status()
+            )));
+        }
+
+        let body: BraveResponse = response
+            .json()
+            .await
+            .map_err(|e| WebSearchError(format!("解析响应失败: {}", e)))?;
+
+        Ok(body
+            .web
+            .map(|web| web.results)
+            .unwrap_or_default()
+            .into_iter()
+            .take(count)
+            .map(|r| SearchResultItem {
+                title: clean_html(&r.title),
+                url: r.url,
+                snippet: clean_html(&r.description),
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,151 @@
+use super::SearchResultItem;
+use crate::config::SearchConfig;
+use crate::db::search_cache_model::SaveSearchRequest;
+use crate::db::search_cache_service::SearchCacheService;
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+use tracing::{error, info};
+
+struct CacheEntry {
+    provider: String,
+    results: Vec<SearchResultItem>,
+    created_at: DateTime<Utc>,
+}
+
+pub struct SearchCache {
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    service: SearchCacheService,
+    ttl_secs: u64,
+    max_entries: usize,
+}
+
+impl SearchCache {
+    pub fn new(config: &SearchConfig, service: SearchCacheService) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            service,
+            ttl_secs: config.cache_ttl_secs,
+            max_entries: config.cache_max_entries,
+        }
+    }
+
+    pub fn normalize(query: &str) -> String {
+        query
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    }
+
+    pub async fn get(&self, key: &str) -> Option<(String, Vec<SearchResultItem>)> {
+        if self.ttl_secs == 0 {
+            return None;
+        }
+
+        let ttl = TimeDelta::seconds(self.ttl_secs as i64);
+
+        {
+            let mut entries = self.entries.lock().await;
+            if let Some(entry) = entries.get(key) {
+                if Utc::now() - entry.created_at < ttl {
+                    info!("搜索缓存命中(内存): query={}", key);
+                    return Some((entry.provider.clone(), entry.results.clone()));
+                }
+                entries.remove(key);
+            }
+        }
+
+        match self.service.get(key, self.ttl_secs).await {
+            Ok(Some(cached)) => {
+                match serde_json::from_str::<Vec<SearchResultItem>>(&cached.results) {
+                    Ok(results) => {
+                        info!("搜索缓存命中(数据库): query={}", key);
+
+                        let created_at =
+                            NaiveDateTime::parse_from_str(&cached.created_at, "%Y-%m-%d %H:%M:%S")
+                                .map(|t| t.and_utc())
+                                .unwrap_or_else(|_| Utc::now());
+                        self.insert_memory(key, &cached.provider, &results, created_at)
+                            .await;
+
+                        Some((cached.provider, results))
+                    }
+                    Err(e) => {
+                        error!("解析搜索缓存失败: query={}, error={}", key, e);
+                        None
+                    }
+                }
+            }
+            Ok(None) => {
+                info!("搜索缓存未命中: query={}", key);
+                None
+            }
+            Err(e) => {
+                error!("查询搜索缓存失败: query={}, error={}", key, e);
+                None
+            }
+        }
+    }
+
+    pub async fn put(&self, key: &str, provider: &str, results: &[SearchResultItem]) {
+        if self.ttl_secs == 0 {
+            return;
+        }
+
+        self.insert_memory(key, provider, results, Utc::now()).await;
+
+        let results = match serde_json::to_string(results) {
+            Ok(results) => results,
+            Err(e) => {
+                error!("序列化搜索结果失败: {}", e);
+                return;
+            }
+        };
+
+        let req = SaveSearchRequest {
+            query_key: key.to_string(),
+            provider: provider.to_string(),
+            results,
+        };
+
+        if let Err(e) = self.service.save(req).await {
+            error!("写入搜索缓存失败: query={}, error={}", key, e);
+        }
+
+        if let Err(e) = self.service.prune(self.ttl_secs, self.max_entries).await {
+            error!("清理搜索缓存失败: {}", e);
+        }
+    }
+
+    async fn insert_memory(
+        &self,
+        key: &str,
+        provider: &str,
+        results: &[SearchResultItem],
+        created_at: DateTime<Utc>,
+    ) {
+        let mut entries = self.entries.lock().await;
+
+        while entries.len() >= self.max_entries && !entries.contains_key(key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.created_at)
+                .map(|(key, _)| key.clone());
+
+            match oldest {
+                Some(oldest) => entries.remove(&oldest),
+                None => break,
+            };
+        }
+
+        entries.insert(
+            key.to_string(),
+            CacheEntry {
+                provider: provider.to_string(),
+                results: results.to_vec(),
+                created_at,
+            },
+        );
+    }
+}
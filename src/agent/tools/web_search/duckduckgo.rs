@@ -0,0 +1,90 @@
+use super::provider::{SearchProvider, USER_AGENT, clean_html};
+use super::{SearchResultItem, WebSearchError};
+use async_trait::async_trait;
+
+pub struct DuckDuckGo {
+    client: reqwest::Client,
+}
+
+impl DuckDuckGo {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    fn parse_html(html: &str, count: usize) -> Vec<SearchResultItem> {
+        let mut results = Vec::new();
+
+        for div in html.split("result__body").skip(1) {
+            if results.len() >= count {
+                break;
+            }
+
+            if let Some(title) = Self::extract_between(div, "result__a\">", "</a>") {
+                let url = Self::extract_between(div, "uddg=", "\"")
+                    .map(|u| u.split("&amp;").next().unwrap_or_default().to_string())
+                    .and_then(|u| urlencoding::decode(&u).ok().map(|s| s.to_string()))
+                    .unwrap_or_default();
+
+                let snippet =
+                    Self::extract_between(div, "result__snippet\">", "</a>").unwrap_or_default();
+
+                if !title.is_empty() && !url.is_empty() {
+                    results.push(SearchResultItem {
+                        title: clean_html(&title),
+                        url,
+                        snippet: clean_html(&snippet),
+                    });
+                }
+            }
+        }
+
+        results
+    }
+
+    fn extract_between(text: &str, start: &str, end: &str) -> Option<String> {
+        let start_idx = text.find(start)? + start.len();
+        let remaining = &text[start_idx..];
+        let end_idx = remaining.find(end)?;
+        Some(remaining[..end_idx].to_string())
+    }
+}
+
+#[async_trait]
+impl SearchProvider for DuckDuckGo {
+    fn name(&self) -> &'static str {
+        "duckduckgo"
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        count: usize,
+    ) -> Result<Vec<SearchResultItem>, WebSearchError> {
+        let url = format!(
+            "https://html.duckduckgo.com/html/?q={}",
+            urlencoding::encode(query)
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .header("User-Agent", USER_AGENT)
+            .send()
+            .await
+            .map_err(|e| WebSearchError(format!("请求失败: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(WebSearchError(format!(
+                "请求失败，状态码: {}",
+                response.status()
+            )));
+        }
+
+        let html = response
+            .text()
+            .await
+            .map_err(|e| WebSearchError(format!("读取响应失败: {}", e)))?;
+
+        Ok(Self::parse_html(&html, count))
+    }
+}
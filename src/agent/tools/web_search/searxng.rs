@@ -0,0 +1,82 @@
+use super::provider::{SearchProvider, USER_AGENT};
+use super::{SearchResultItem, WebSearchError};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct SearxngResponse {
+    #[serde(default)]
+    results: Vec<SearxngResult>,
+}
+
+#[derive(Deserialize)]
+struct SearxngResult {
+    title: String,
+    url: String,
+    #[serde(default)]
+    content: String,
+}
+
+pub struct Searxng {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl Searxng {
+    pub fn new(client: reqwest::Client, base_url: &str) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl SearchProvider for Searxng {
+    fn name(&self) -> &'static str {
+        "searxng"
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        count: usize,
+    ) -> Result<Vec<SearchResultItem>, WebSearchError> {
+        let url = format!(
+            "{}/search?q={}&format=json",
+            self.base_url,
+            urlencoding::encode(query)
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .header("User-Agent", USER_AGENT)
+            .send()
+            .await
+            .map_err(|e| WebSearchError(format!("请求失败: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(WebSearchError(format!(
+                "请求失败，状态码: {}",
+                response.status()
+            )));
+        }
+
+        let body: SearxngResponse = response
+            .json()
+            .await
+            .map_err(|e| WebSearchError(format!("解析响应失败: {}", e)))?;
+
+        Ok(body
+            .results
+            .into_iter()
+            .take(count)
+            .map(|r| SearchResultItem {
+                title: r.title,
+                url: r.url,
+                snippet: r.content,
+            })
+            .collect())
+    }
+}
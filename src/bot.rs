@@ -3,7 +3,9 @@ mod message_handle;
 
 use crate::agent::Agent;
 use crate::config::BotConfig;
+use crate::db::group_service::GroupService;
 use crate::db::user_service::UserService;
+use crate::scheduler::SchedulerManager;
 use anyhow::{Result, bail};
 use event_handle::Handler;
 use milky_rust_sdk::MilkyClient;
@@ -13,20 +15,29 @@ use tokio::sync::{Semaphore, mpsc, oneshot};
 use tokio::task::{JoinHandle, JoinSet};
 use tracing::{debug, error, info};
 
+/// 处理消息需要用到的服务，由 Bot 传给各个消息处理器
+#[derive(Clone)]
+pub struct BotServices {
+    pub user_service: UserService,
+    pub group_service: GroupService,
+    pub agent: Arc<Agent>,
+    pub scheduler_manager: Arc<SchedulerManager>,
+}
+
 pub struct Bot {
     client: Arc<MilkyClient>,
     event_rx: mpsc::Receiver<Event>,
     handler: Handler,
-    max_concurrent_tasks: usize,
+    semaphore: Arc<Semaphore>,
 }
 
 impl Bot {
     pub async fn new(
-        bot_config: &BotConfig,
-        user_service: UserService,
+        semaphore: Arc<Semaphore>,
+        services: BotServices,
         client: Arc<MilkyClient>,
         event_rx: mpsc::Receiver<Event>,
-        agent: Arc<Agent>,
+        bot_config: &BotConfig,
     ) -> Result<Self> {
         if let Err(e) = client.connect_events().await {
             bail!("未能连接到事件流: {e}");
@@ -34,13 +45,13 @@ impl Bot {
 
         info!("成功链接到Milky事件流");
 
-        let handler = Handler::new(user_service, Arc::clone(&client), agent);
+        let handler = Handler::new(services, Arc::clone(&client), bot_config)?;
 
         Ok(Self {
             client,
             event_rx,
             handler,
-            max_concurrent_tasks: bot_config.max_concurrent_tasks,
+            semaphore,
         })
     }
 
@@ -49,7 +60,7 @@ impl Bot {
         let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
 
         let handler = self.handler.clone();
-        let semaphore = Arc::new(Semaphore::new(self.max_concurrent_tasks));
+        let semaphore = Arc::clone(&self.semaphore);
 
         let event_task = tokio::spawn(async move {
             let _ = ready_tx.send(());
@@ -1,11 +1,11 @@
-use crate::agent::Agent;
-use crate::db::user_service::UserService;
+use crate::config::BotConfig;
 use anyhow::Result;
 use milky_rust_sdk::MilkyClient;
 use milky_rust_sdk::prelude::{Event, EventKind};
 use std::sync::Arc;
 use tracing::warn;
 
+use super::BotServices;
 use super::message_handle::MessageHandler;
 
 #[derive(Clone)]
@@ -14,10 +14,14 @@ pub struct Handler {
 }
 
 impl Handler {
-    pub fn new(user_service: UserService, client: Arc<MilkyClient>, agent: Arc<Agent>) -> Self {
-        Self {
-            message_handler: MessageHandler::new(user_service, client, agent),
-        }
+    pub fn new(
+        services: BotServices,
+        client: Arc<MilkyClient>,
+        bot_config: &BotConfig,
+    ) -> Result<Self> {
+        Ok(Self {
+            message_handler: MessageHandler::new(services, client, bot_config)?,
+        })
     }
 
     pub async fn handle_event(&self, event: Event) -> Result<()> {
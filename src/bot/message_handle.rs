@@ -1,5 +1,5 @@
-use crate::agent::Agent;
-use crate::db::user_service::UserService;
+use super::BotServices;
+use crate::config::BotConfig;
 use anyhow::Result;
 use milky_rust_sdk::MilkyClient;
 use milky_rust_sdk::prelude::MessageEvent;
@@ -21,12 +21,34 @@ pub struct MessageHandler {
 }
 
 impl MessageHandler {
-    pub fn new(user_service: UserService, client: Arc<MilkyClient>, agent: Arc<Agent>) -> Self {
-        Self {
-            friend_handler: FriendMessageHandler::new(user_service, Arc::clone(&client), agent),
-            group_handler: GroupMessageHandler::new(Arc::clone(&client)),
-            temp_handler: TempMessageHandler::new(client),
-        }
+    pub fn new(
+        services: BotServices,
+        client: Arc<MilkyClient>,
+        bot_config: &BotConfig,
+    ) -> Result<Self> {
+        let BotServices {
+            user_service,
+            group_service,
+            agent,
+            scheduler_manager,
+        } = services;
+
+        Ok(Self {
+            friend_handler: FriendMessageHandler::new(
+                user_service.clone(),
+                Arc::clone(&client),
+                Arc::clone(&agent),
+                scheduler_manager,
+            )?,
+            group_handler: GroupMessageHandler::new(
+                user_service.clone(),
+                group_service,
+                Arc::clone(&client),
+                Arc::clone(&agent),
+                bot_config,
+            ),
+            temp_handler: TempMessageHandler::new(user_service, client, agent, bot_config),
+        })
     }
 
     pub async fn handle(&self, message: MessageEvent) -> Result<()> {
@@ -1,33 +1,57 @@
 use crate::agent::Agent;
-use crate::db::user_model::CreateUserRequest;
+use crate::db::user_model::{CreateUserRequest, UpdateActivityRequest};
 use crate::db::user_service::UserService;
+use crate::scheduler::SchedulerManager;
 use anyhow::Result;
 use milky_rust_sdk::MilkyClient;
-use milky_rust_sdk::prelude::FriendMessage;
+use milky_rust_sdk::prelude::{FriendMessage, IncomingSegment};
 use milky_rust_sdk::utils::get_plain_text_from_segments;
 use std::sync::Arc;
-use tracing::error;
+use tracing::{debug, error};
 
+use friend_calendar::FriendCalendarHandler;
 use friend_chat::FriendChatHandler;
 use friend_command::FriendCommandHandler;
+use friend_reminder::FriendReminderHandler;
 
+mod friend_calendar;
 mod friend_chat;
 mod friend_command;
+mod friend_reminder;
+
+/// 保存用户最后一条消息时的最大字符数，用于主动关怀时提供上下文
+const LAST_MESSAGE_MAX_CHARS: usize = 200;
 
 #[derive(Clone)]
 pub struct FriendMessageHandler {
     user_service: UserService,
     command_handler: FriendCommandHandler,
+    calendar_handler: FriendCalendarHandler,
+    reminder_handler: FriendReminderHandler,
     chat_handler: FriendChatHandler,
 }
 
 impl FriendMessageHandler {
-    pub fn new(user_service: UserService, client: Arc<MilkyClient>, agent: Arc<Agent>) -> Self {
-        Self {
+    pub fn new(
+        user_service: UserService,
+        client: Arc<MilkyClient>,
+        agent: Arc<Agent>,
+        scheduler_manager: Arc<SchedulerManager>,
+    ) -> Result<Self> {
+        Ok(Self {
             user_service: user_service.clone(),
-            command_handler: FriendCommandHandler::new(user_service, Arc::clone(&client)),
+            command_handler: FriendCommandHandler::new(
+                user_service,
+                Arc::clone(&client),
+                Arc::clone(&scheduler_manager),
+            ),
+            calendar_handler: FriendCalendarHandler::new(
+                Arc::clone(&client),
+                Arc::clone(&scheduler_manager),
+            )?,
+            reminder_handler: FriendReminderHandler::new(client, scheduler_manager),
             chat_handler: FriendChatHandler::new(agent),
-        }
+        })
     }
 
     pub async fn handle(&self, msg: FriendMessage) -> Result<()> {
@@ -47,10 +71,43 @@ impl FriendMessageHandler {
         };
 
         let text_content = get_plain_text_from_segments(&msg.message.segments);
+        let is_command = text_content.starts_with('#');
+
+        let last_message = (!is_command && !text_content.trim().is_empty())
+            .then(|| text_content.chars().take(LAST_MESSAGE_MAX_CHARS).collect());
+        if let Err(e) = self
+            .user_service
+            .update_activity(UpdateActivityRequest {
+                id: user.id,
+                last_message,
+            })
+            .await
+        {
+            error!("更新用户活跃时间失败: {e}");
+        }
+
+        let quoted_seq = msg
+            .message
+            .segments
+            .iter()
+            .find_map(|segment| match segment {
+                IncomingSegment::Reply { message_seq } => Some(*message_seq),
+                _ => None,
+            });
 
-        if text_content.starts_with('#') {
+        if is_command {
             self.command_handler.handle(user.id, &text_content).await?;
-        } else {
+        } else if self
+            .calendar_handler
+            .handle(&user, &msg.message.segments)
+            .await?
+        {
+            debug!("日历文件已处理: user_id={}", user.id);
+        } else if !self
+            .reminder_handler
+            .handle(&user, quoted_seq, &text_content)
+            .await?
+        {
             self.chat_handler.handle(&user, &text_content).await?;
         }
 
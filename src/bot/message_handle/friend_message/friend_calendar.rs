@@ -0,0 +1,114 @@
+use crate::db::user_model::User;
+use crate::scheduler::SchedulerManager;
+use crate::utils::send_message;
+use anyhow::{Context, Result, anyhow, bail};
+use milky_rust_sdk::MilkyClient;
+use milky_rust_sdk::prelude::IncomingSegment;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error};
+
+/// 允许导入的日历文件大小上限
+const MAX_CALENDAR_FILE_BYTES: i64 = 1024 * 1024;
+
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 处理用户发来的 .ics 日历文件，解析后生成导入预览
+#[derive(Clone)]
+pub struct FriendCalendarHandler {
+    client: Arc<MilkyClient>,
+    http: reqwest::Client,
+    scheduler_manager: Arc<SchedulerManager>,
+}
+
+impl FriendCalendarHandler {
+    pub fn new(client: Arc<MilkyClient>, scheduler_manager: Arc<SchedulerManager>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(DOWNLOAD_TIMEOUT)
+            .build()
+            .context("创建日历下载客户端失败")?;
+
+        Ok(Self {
+            client,
+            http,
+            scheduler_manager,
+        })
+    }
+
+    /// 消息中包含日历文件时返回 true
+    pub async fn handle(&self, user: &User, segments: &[IncomingSegment]) -> Result<bool> {
+        let Some((file_id, file_name, file_size, file_hash)) =
+            segments.iter().find_map(|segment| match segment {
+                IncomingSegment::File {
+                    file_id,
+                    file_name,
+                    file_size,
+                    file_hash,
+                } if file_name.to_ascii_lowercase().ends_with(".ics") => {
+                    Some((file_id, file_name, *file_size, file_hash))
+                }
+                _ => None,
+            })
+        else {
+            return Ok(false);
+        };
+
+        debug!(
+            "收到日历文件: user_id={}, file_name={}, size={}",
+            user.id, file_name, file_size
+        );
+
+        let message = match self
+            .preview(user.id, file_id, file_size, file_hash.as_deref())
+            .await
+        {
+            Ok(preview) => preview,
+            Err(e) => {
+                error!("解析日历文件失败: user_id={}, error={:#}", user.id, e);
+                format!("日历导入失败: {}", e)
+            }
+        };
+
+        send_message(self.client.clone(), user.id, vec![message]).await;
+        Ok(true)
+    }
+
+    async fn preview(
+        &self,
+        user_id: i64,
+        file_id: &str,
+        file_size: i64,
+        file_hash: Option<&str>,
+    ) -> Result<String> {
+        if file_size > MAX_CALENDAR_FILE_BYTES {
+            bail!("文件过大，日历文件不能超过 1MB");
+        }
+        let file_hash = file_hash.ok_or_else(|| anyhow!("无法获取文件信息"))?;
+
+        let download_url = self
+            .client
+            .get_private_file_download_url(user_id, file_id.to_string(), file_hash.to_string())
+            .await?
+            .download_url;
+        let mut response = self
+            .http
+            .get(&download_url)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        // 文件大小来自消息本身，实际下载时同样限制读取的字节数
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > MAX_CALENDAR_FILE_BYTES as usize {
+                bail!("文件过大，日历文件不能超过 1MB");
+            }
+            body.extend_from_slice(&chunk);
+        }
+        let content = String::from_utf8(body).map_err(|_| anyhow!("日历文件不是 UTF-8 编码"))?;
+
+        self.scheduler_manager
+            .preview_import(user_id, &content)
+            .await
+    }
+}
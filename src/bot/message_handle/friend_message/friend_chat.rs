@@ -14,6 +14,7 @@ impl FriendChatHandler {
     }
 
     pub async fn handle(&self, user: &User, message: &str) -> Result<()> {
-        self.agent.deal(user, message).await
+        self.agent.deal(user, message, None).await?;
+        Ok(())
     }
 }
@@ -1,9 +1,16 @@
+use crate::db::scheduler_model::ScheduledTask;
 use crate::db::user_model::{
-    CreateCustomPromptRequest, CreateMasterRequest, UpdateUserRequest, UserRelation,
+    CreateCustomPromptRequest, CreateMasterRequest, UpdateDndRequest, UpdateQuietHoursRequest,
+    UpdateUserRequest, UserRelation,
 };
 use crate::db::user_service::UserService;
-use crate::utils::send_message;
-use anyhow::Result;
+use crate::scheduler::SchedulerManager;
+use crate::utils::{format_db_datetime, format_local_datetime, parse_db_datetime, send_message};
+use anyhow::{Result, anyhow, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{NaiveTime, TimeDelta, Utc};
+use chrono_tz::Tz;
 use milky_rust_sdk::MilkyClient;
 use std::sync::Arc;
 
@@ -12,6 +19,15 @@ pub enum Command {
     CreateMaster,
     CreateCustomPrompt(String),
     UpdateUser(i64, String),
+    Timezone(Option<String>),
+    Tasks,
+    Today,
+    Failures(Option<i64>),
+    Greeting(Option<String>),
+    Quiet(Option<String>),
+    Dnd(Option<String>),
+    Export,
+    Import(Option<String>),
     All,
     Unknown(String),
 }
@@ -47,6 +63,15 @@ impl Command {
                     Command::Unknown(cmd.to_string())
                 }
             }
+            "#timezone" => Command::Timezone(args.map(|tz| tz.trim().to_string())),
+            "#tasks" => Command::Tasks,
+            "#today" => Command::Today,
+            "#failures" => Command::Failures(args.and_then(|n| n.trim().parse::<i64>().ok())),
+            "#greeting" => Command::Greeting(args.map(|a| a.trim().to_string())),
+            "#quiet" => Command::Quiet(args.map(|a| a.trim().to_string())),
+            "#dnd" => Command::Dnd(args.map(|a| a.trim().to_string())),
+            "#export" => Command::Export,
+            "#import" => Command::Import(args.map(|a| a.trim().to_string())),
             "#all" => Command::All,
             _ => Command::Unknown(cmd.to_string()),
         }
@@ -57,13 +82,19 @@ impl Command {
 pub struct FriendCommandHandler {
     user_service: UserService,
     client: Arc<MilkyClient>,
+    scheduler_manager: Arc<SchedulerManager>,
 }
 
 impl FriendCommandHandler {
-    pub fn new(user_service: UserService, client: Arc<MilkyClient>) -> Self {
+    pub fn new(
+        user_service: UserService,
+        client: Arc<MilkyClient>,
+        scheduler_manager: Arc<SchedulerManager>,
+    ) -> Self {
         Self {
             user_service,
             client,
+            scheduler_manager,
         }
     }
 
@@ -95,6 +126,15 @@ impl FriendCommandHandler {
                 self.cmd_update_user(user_id, target_user_id, &relation)
                     .await
             }
+            Command::Timezone(timezone) => self.cmd_timezone(user_id, timezone).await,
+            Command::Tasks => self.cmd_tasks(user_id).await,
+            Command::Today => self.cmd_today(user_id).await,
+            Command::Failures(limit) => self.cmd_failures(user_id, limit).await,
+            Command::Greeting(option) => self.cmd_greeting(user_id, option).await,
+            Command::Quiet(option) => self.cmd_quiet(user_id, option).await,
+            Command::Dnd(option) => self.cmd_dnd(user_id, option).await,
+            Command::Export => self.cmd_export(user_id).await,
+            Command::Import(option) => self.cmd_import(user_id, option).await,
             Command::All => self.cmd_all(user_id).await,
             Command::Unknown(cmd_str) => {
                 if cmd_str.starts_with("#create_custom_prompt") {
@@ -189,13 +229,393 @@ impl FriendCommandHandler {
         Ok(())
     }
 
+    async fn cmd_timezone(&self, user_id: i64, timezone: Option<String>) -> Result<()> {
+        let message = match timezone {
+            Some(timezone) => {
+                let user = self
+                    .scheduler_manager
+                    .set_user_timezone(user_id, &timezone)
+                    .await?;
+                format!("时区已设置为 {}", user.timezone.unwrap_or_default())
+            }
+            None => {
+                let timezone = self.scheduler_manager.timezone_for(user_id).await;
+                format!(
+                    "当前时区: {}\n用法: #timezone [时区]，例如 #timezone Asia/Shanghai",
+                    timezone
+                )
+            }
+        };
+
+        send_message(self.client.clone(), user_id, vec![message]).await;
+        Ok(())
+    }
+
+    async fn cmd_tasks(&self, user_id: i64) -> Result<()> {
+        let tasks = self.scheduler_manager.list_tasks(user_id).await?;
+        let timezone = self.scheduler_manager.timezone_for(user_id).await;
+
+        let message = if tasks.is_empty() {
+            "暂无定时任务".to_string()
+        } else {
+            let lines: Vec<String> = tasks
+                .iter()
+                .map(|task| Self::format_task(task, timezone))
+                .collect();
+            format!("定时任务列表（{}）:\n{}", timezone, lines.join("\n"))
+        };
+
+        send_message(self.client.clone(), user_id, vec![message]).await;
+        Ok(())
+    }
+
+    async fn cmd_today(&self, user_id: i64) -> Result<()> {
+        let tasks = self.scheduler_manager.list_today_tasks(user_id).await?;
+        let timezone = self.scheduler_manager.timezone_for(user_id).await;
+
+        let message = if tasks.is_empty() {
+            "今天没有待执行的定时任务".to_string()
+        } else {
+            let lines: Vec<String> = tasks
+                .iter()
+                .map(|task| Self::format_task(task, timezone))
+                .collect();
+            format!("今日待执行任务（{}）:\n{}", timezone, lines.join("\n"))
+        };
+
+        send_message(self.client.clone(), user_id, vec![message]).await;
+        Ok(())
+    }
+
+    async fn cmd_failures(&self, user_id: i64, limit: Option<i64>) -> Result<()> {
+        let limit = limit.unwrap_or(10).clamp(1, 50);
+        let runs = self
+            .scheduler_manager
+            .recent_failures(user_id, limit)
+            .await?;
+
+        let message = if runs.is_empty() {
+            "最近没有失败的任务执行记录".to_string()
+        } else {
+            let lines: Vec<String> = runs
+                .iter()
+                .map(|run| {
+                    let error: String = run
+                        .error
+                        .as_deref()
+                        .unwrap_or_default()
+                        .chars()
+                        .take(60)
+                        .collect();
+                    format!(
+                        "[{}] 任务 {} 用户 {} 第{}次 {} - {}",
+                        run.started_at,
+                        run.task_id
+                            .map(|id| id.to_string())
+                            .unwrap_or_else(|| "-".to_string()),
+                        run.target_user_id,
+                        run.attempt + 1,
+                        run.status.as_str(),
+                        error
+                    )
+                })
+                .collect();
+            format!("最近失败的任务执行（UTC）:\n{}", lines.join("\n"))
+        };
+
+        send_message(self.client.clone(), user_id, vec![message]).await;
+        Ok(())
+    }
+
+    async fn cmd_greeting(&self, user_id: i64, option: Option<String>) -> Result<()> {
+        let user = self
+            .user_service
+            .get_user(user_id)
+            .await?
+            .ok_or_else(|| anyhow!("用户 ID {} 不存在", user_id))?;
+        let current_categories = user
+            .greeting_categories
+            .as_deref()
+            .map(|c| c.split(',').map(|s| s.to_string()).collect::<Vec<_>>());
+
+        let message = match option.as_deref() {
+            None | Some("") => {
+                let status = if user.greetings_enabled {
+                    "开启"
+                } else {
+                    "关闭"
+                };
+                format!(
+                    "主动问候: {}\n接收类别: {}\n可选类别: {}\n用法: #greeting on|off|all|类别1,类别2",
+                    status,
+                    user.greeting_categories.as_deref().unwrap_or("全部"),
+                    self.scheduler_manager.greeting_categories().join(", ")
+                )
+            }
+            Some("on") => {
+                self.scheduler_manager
+                    .set_greeting_preference(user_id, true, current_categories)
+                    .await?;
+                "已开启主动问候".to_string()
+            }
+            Some("off") => {
+                self.scheduler_manager
+                    .set_greeting_preference(user_id, false, current_categories)
+                    .await?;
+                "已关闭主动问候".to_string()
+            }
+            Some("all") => {
+                self.scheduler_manager
+                    .set_greeting_preference(user_id, true, None)
+                    .await?;
+                "已设置为接收全部类别的问候".to_string()
+            }
+            Some(categories) => {
+                let categories: Vec<String> = categories
+                    .split([',', '，', ' '])
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
+                let user = self
+                    .scheduler_manager
+                    .set_greeting_preference(user_id, true, Some(categories))
+                    .await?;
+                format!(
+                    "已设置问候类别: {}",
+                    user.greeting_categories.unwrap_or_default()
+                )
+            }
+        };
+
+        send_message(self.client.clone(), user_id, vec![message]).await;
+        Ok(())
+    }
+
+    async fn cmd_quiet(&self, user_id: i64, option: Option<String>) -> Result<()> {
+        let message = match option.as_deref() {
+            None | Some("") => {
+                let user = self
+                    .user_service
+                    .get_user(user_id)
+                    .await?
+                    .ok_or_else(|| anyhow!("用户 ID {} 不存在", user_id))?;
+                match (user.quiet_start, user.quiet_end) {
+                    (Some(start), Some(end)) => format!(
+                        "安静时段: {}-{}\n用法: #quiet 23:00-07:00 或 #quiet off",
+                        start, end
+                    ),
+                    _ => "未设置安静时段\n用法: #quiet 23:00-07:00 或 #quiet off".to_string(),
+                }
+            }
+            Some("off") => {
+                self.user_service
+                    .update_quiet_hours(UpdateQuietHoursRequest {
+                        id: user_id,
+                        quiet_start: None,
+                        quiet_end: None,
+                    })
+                    .await?;
+                "已关闭安静时段".to_string()
+            }
+            Some(range) => {
+                let (start, end) = range
+                    .split_once('-')
+                    .ok_or_else(|| anyhow!("格式错误，请使用 #quiet 23:00-07:00"))?;
+                let parse = |value: &str| {
+                    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+                        .map(|t| t.format("%H:%M").to_string())
+                        .map_err(|_| anyhow!("无法解析时刻 '{}'，请使用 HH:MM 格式", value))
+                };
+                let (start, end) = (parse(start)?, parse(end)?);
+                if start == end {
+                    bail!("安静时段的开始和结束时间不能相同");
+                }
+
+                self.user_service
+                    .update_quiet_hours(UpdateQuietHoursRequest {
+                        id: user_id,
+                        quiet_start: Some(start.clone()),
+                        quiet_end: Some(end.clone()),
+                    })
+                    .await?;
+                format!(
+                    "安静时段已设置为 {}-{}，期间的提醒会推迟，问候会跳过",
+                    start, end
+                )
+            }
+        };
+
+        send_message(self.client.clone(), user_id, vec![message]).await;
+        Ok(())
+    }
+
+    async fn cmd_dnd(&self, user_id: i64, option: Option<String>) -> Result<()> {
+        let timezone = self.scheduler_manager.timezone_for(user_id).await;
+
+        let message = match option.as_deref() {
+            None | Some("") => {
+                let user = self
+                    .user_service
+                    .get_user(user_id)
+                    .await?
+                    .ok_or_else(|| anyhow!("用户 ID {} 不存在", user_id))?;
+                let until = user
+                    .dnd_until
+                    .as_deref()
+                    .and_then(parse_db_datetime)
+                    .filter(|until| *until > Utc::now());
+                match until {
+                    Some(until) => format!(
+                        "免打扰中，直到 {}\n用法: #dnd 2h 或 #dnd off",
+                        until.with_timezone(&timezone).format("%Y-%m-%d %H:%M")
+                    ),
+                    None => {
+                        "未开启免打扰\n用法: #dnd 2h、#dnd 30m、#dnd 1d 或 #dnd off".to_string()
+                    }
+                }
+            }
+            Some("off") => {
+                self.user_service
+                    .update_dnd(UpdateDndRequest {
+                        id: user_id,
+                        dnd_until: None,
+                    })
+                    .await?;
+                "已关闭免打扰".to_string()
+            }
+            Some(duration) => {
+                let duration = Self::parse_duration(duration)?;
+                let until = Utc::now() + duration;
+
+                self.user_service
+                    .update_dnd(UpdateDndRequest {
+                        id: user_id,
+                        dnd_until: Some(format_db_datetime(&until)),
+                    })
+                    .await?;
+                format!(
+                    "已开启免打扰，直到 {}",
+                    until.with_timezone(&timezone).format("%Y-%m-%d %H:%M")
+                )
+            }
+        };
+
+        send_message(self.client.clone(), user_id, vec![message]).await;
+        Ok(())
+    }
+
+    async fn cmd_export(&self, user_id: i64) -> Result<()> {
+        let export = self.scheduler_manager.export_calendar(user_id).await?;
+        if export.exported == 0 {
+            send_message(
+                self.client.clone(),
+                user_id,
+                vec!["没有可以导出的提醒".to_string()],
+            )
+            .await;
+            return Ok(());
+        }
+
+        self.client
+            .upload_private_file(
+                user_id,
+                format!("base64://{}", STANDARD.encode(export.content)),
+                "reminders.ics".to_string(),
+            )
+            .await?;
+
+        let mut message = format!(
+            "已导出 {} 个提醒，打开文件即可导入手机日历",
+            export.exported
+        );
+        if export.skipped > 0 {
+            message.push_str(&format!(
+                "\n{} 个提醒的重复规则无法用日历表示，未导出",
+                export.skipped
+            ));
+        }
+
+        send_message(self.client.clone(), user_id, vec![message]).await;
+        Ok(())
+    }
+
+    async fn cmd_import(&self, user_id: i64, option: Option<String>) -> Result<()> {
+        let message = match option.as_deref() {
+            Some("confirm") => self.scheduler_manager.confirm_import(user_id).await?,
+            Some("cancel") => {
+                if self.scheduler_manager.cancel_import(user_id).await {
+                    "已取消日历导入".to_string()
+                } else {
+                    "没有待确认的日历导入".to_string()
+                }
+            }
+            _ => "直接发送 .ics 日历文件即可预览导入内容\n用法: #import confirm 确认导入，#import cancel 取消"
+                .to_string(),
+        };
+
+        send_message(self.client.clone(), user_id, vec![message]).await;
+        Ok(())
+    }
+
+    fn parse_duration(value: &str) -> Result<TimeDelta> {
+        let value = value.trim().to_ascii_lowercase();
+        let split = value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len());
+        let (amount, unit) = value.split_at(split);
+        let invalid = || anyhow!("无法解析时长 '{}'，例如 2h、30m、1d", value);
+        let amount: i64 = amount.parse().map_err(|_| invalid())?;
+
+        let duration = match unit {
+            "m" | "min" => TimeDelta::try_minutes(amount),
+            "h" | "" => TimeDelta::try_hours(amount),
+            "d" => TimeDelta::try_days(amount),
+            _ => None,
+        }
+        .ok_or_else(invalid)?;
+
+        if duration <= TimeDelta::zero() || TimeDelta::try_days(30).is_none_or(|max| duration > max)
+        {
+            bail!("免打扰时长必须在 30 天以内");
+        }
+        Ok(duration)
+    }
+
+    fn format_task(task: &ScheduledTask, timezone: Tz) -> String {
+        let status = if task.enabled { "启用" } else { "暂停" };
+        let next_run_at = task
+            .next_run_at
+            .as_deref()
+            .and_then(|t| format_local_datetime(t, timezone))
+            .unwrap_or_else(|| "无".to_string());
+        let content: String = task.content.chars().take(30).collect();
+
+        format!(
+            "[{}] {} {} 下次: {} - {}",
+            task.id,
+            task.frequency.as_str(),
+            status,
+            next_run_at,
+            content
+        )
+    }
+
     async fn cmd_all(&self, user_id: i64) -> Result<()> {
         let message = [
             "可用命令列表:".to_string(),
             "1. #create_master - 创建 master 用户".to_string(),
             "2. #create_custom_prompt [prompt] - 设置自定义提示词".to_string(),
             "3. #update_user [user_id] [relation] - 修改用户关系".to_string(),
-            "4. #all - 查看所有命令".to_string(),
+            "4. #timezone [时区] - 查看或设置时区".to_string(),
+            "5. #tasks - 查看我的定时任务".to_string(),
+            "6. #today - 查看今日待执行的任务".to_string(),
+            "7. #failures [数量] - 查看最近失败的任务执行（仅 master）".to_string(),
+            "8. #greeting [on|off|all|类别] - 设置主动问候".to_string(),
+            "9. #quiet [开始-结束|off] - 设置每日安静时段".to_string(),
+            "10. #dnd [时长|off] - 临时免打扰，例如 #dnd 2h".to_string(),
+            "11. #export - 导出我的提醒为日历文件".to_string(),
+            "12. #import [confirm|cancel] - 确认或取消导入发送的日历文件".to_string(),
+            "13. #all - 查看所有命令".to_string(),
         ]
         .join("\n");
 
@@ -0,0 +1,47 @@
+use crate::db::user_model::User;
+use crate::scheduler::SchedulerManager;
+use crate::utils::send_message;
+use anyhow::Result;
+use milky_rust_sdk::MilkyClient;
+use std::sync::Arc;
+use tracing::error;
+
+/// 处理用户对提醒的快捷回复：稍后提醒、完成、取消
+#[derive(Clone)]
+pub struct FriendReminderHandler {
+    client: Arc<MilkyClient>,
+    scheduler_manager: Arc<SchedulerManager>,
+}
+
+impl FriendReminderHandler {
+    pub fn new(client: Arc<MilkyClient>, scheduler_manager: Arc<SchedulerManager>) -> Self {
+        Self {
+            client,
+            scheduler_manager,
+        }
+    }
+
+    /// 消息被识别为对提醒的回复时返回 true
+    pub async fn handle(
+        &self,
+        user: &User,
+        quoted_seq: Option<i64>,
+        message: &str,
+    ) -> Result<bool> {
+        let reply = match self
+            .scheduler_manager
+            .handle_reminder_reply(user.id, quoted_seq, message)
+            .await
+        {
+            Ok(Some(reply)) => reply,
+            Ok(None) => return Ok(false),
+            Err(e) => {
+                error!("处理提醒回复失败: user_id={}, error={}", user.id, e);
+                format!("操作失败: {}", e)
+            }
+        };
+
+        send_message(self.client.clone(), user.id, vec![reply]).await;
+        Ok(true)
+    }
+}
@@ -1,24 +1,222 @@
+use crate::agent::{Agent, GroupContext};
+use crate::config::BotConfig;
+use crate::db::group_model::{CreateGroupRequest, GroupTriggerMode};
+use crate::db::group_service::GroupService;
+use crate::db::user_model::CreateUserRequest;
+use crate::db::user_service::UserService;
 use anyhow::Result;
 use milky_rust_sdk::MilkyClient;
-use milky_rust_sdk::prelude::GroupMessage;
+use milky_rust_sdk::prelude::{GroupMessage, IncomingSegment, MessageScene};
+use milky_rust_sdk::utils::get_plain_text_from_segments;
 use std::sync::Arc;
-use tracing::info;
+use tokio::sync::OnceCell;
+use tracing::{debug, error, info, warn};
+
+use group_command::GroupCommandHandler;
+use group_history::GroupHistory;
+use group_rate_limit::GroupRateLimit;
+
+mod group_command;
+mod group_history;
+mod group_rate_limit;
 
 #[derive(Clone)]
 pub struct GroupMessageHandler {
-    #[allow(dead_code)]
+    user_service: UserService,
+    group_service: GroupService,
+    command_handler: GroupCommandHandler,
     client: Arc<MilkyClient>,
+    agent: Arc<Agent>,
+    keywords: Arc<Vec<String>>,
+    history: GroupHistory,
+    rate_limit: GroupRateLimit,
+    self_id: Arc<OnceCell<i64>>,
 }
 
 impl GroupMessageHandler {
-    pub fn new(client: Arc<MilkyClient>) -> Self {
-        Self { client }
+    pub fn new(
+        user_service: UserService,
+        group_service: GroupService,
+        client: Arc<MilkyClient>,
+        agent: Arc<Agent>,
+        bot_config: &BotConfig,
+    ) -> Self {
+        Self {
+            user_service: user_service.clone(),
+            group_service: group_service.clone(),
+            command_handler: GroupCommandHandler::new(
+                user_service,
+                group_service,
+                Arc::clone(&client),
+            ),
+            client,
+            agent,
+            keywords: Arc::new(
+                bot_config
+                    .group_keywords
+                    .iter()
+                    .map(|keyword| keyword.trim().to_string())
+                    .filter(|keyword| !keyword.is_empty())
+                    .collect(),
+            ),
+            history: GroupHistory::new(
+                bot_config.group_history_size,
+                bot_config.group_history_max_chars,
+            ),
+            rate_limit: GroupRateLimit::default(),
+            self_id: Arc::new(OnceCell::new()),
+        }
     }
 
     pub async fn handle(&self, msg: GroupMessage) -> Result<()> {
         let user_id = msg.message.sender_id;
         let group_id = msg.group.group_id;
-        info!("收到群消息，用户ID: {}, 群ID: {}", user_id, group_id);
+
+        let self_id = *self
+            .self_id
+            .get_or_try_init(|| async { self.client.get_login_info().await.map(|info| info.uin) })
+            .await?;
+
+        let text_content = get_plain_text_from_segments(&msg.message.segments);
+        let text_content = text_content.trim();
+
+        let member = msg.group_member;
+        let name = if member.card.is_empty() {
+            member.nickname.clone()
+        } else {
+            member.card.clone()
+        };
+
+        let history = self.history.recent(group_id);
+        self.history.push(group_id, &name, user_id, text_content);
+
+        if user_id == self_id || text_content.is_empty() {
+            return Ok(());
+        }
+
+        let group = self
+            .group_service
+            .create_group(CreateGroupRequest {
+                id: group_id,
+                name: msg.group.group_name,
+            })
+            .await?;
+
+        if text_content == "#group" || text_content.starts_with("#group ") {
+            return self
+                .command_handler
+                .handle(&group, &member, text_content)
+                .await;
+        }
+
+        if !group.enabled {
+            debug!("群已停用机器人，忽略消息: 群ID: {}", group_id);
+            return Ok(());
+        }
+
+        let triggered = match group.trigger_mode {
+            GroupTriggerMode::All => true,
+            GroupTriggerMode::Mention => {
+                self.is_mentioned(self_id, group_id, &msg.message.segments)
+                    .await
+            }
+            GroupTriggerMode::Keyword => {
+                self.is_mentioned(self_id, group_id, &msg.message.segments)
+                    .await
+                    || self.contains_keyword(text_content)
+            }
+        };
+        if !triggered && !group.proactive {
+            debug!(
+                "群消息未触发机器人，忽略: 用户ID: {}, 群ID: {}",
+                user_id, group_id
+            );
+            return Ok(());
+        }
+
+        if let Some(limit) = group.rate_limit
+            && self.rate_limit.is_exhausted(group_id, limit)
+        {
+            debug!("群回复次数已达上限，忽略消息: 群ID: {}", group_id);
+            return Ok(());
+        }
+
+        if !triggered && !self.rate_limit.try_proactive(group_id, text_content) {
+            debug!("群消息未通过主动发言筛选，忽略: 群ID: {}", group_id);
+            return Ok(());
+        }
+
+        info!(
+            "收到群消息，用户ID: {}, 群ID: {}, 触发: {}",
+            user_id, group_id, triggered
+        );
+
+        let user = match self
+            .user_service
+            .create_stranger(CreateUserRequest {
+                id: user_id,
+                name: member.nickname,
+            })
+            .await
+        {
+            Ok(user) => user,
+            Err(e) => {
+                error!("创建用户失败: {e}");
+                return Err(e);
+            }
+        };
+
+        let allowed_tools = group.allowed_tools();
+        let context = GroupContext {
+            group_id,
+            group_name: group.name,
+            sender_card: name,
+            history,
+            persona: group.persona,
+            allowed_tools,
+            proactive: !triggered,
+        };
+        let message_seqs = self.agent.deal_group(&user, &context, text_content).await?;
+        if !message_seqs.is_empty() {
+            self.rate_limit.record_reply(group_id);
+        }
         Ok(())
     }
+
+    /// 消息 @了机器人或回复了机器人的消息
+    async fn is_mentioned(
+        &self,
+        self_id: i64,
+        group_id: i64,
+        segments: &[IncomingSegment],
+    ) -> bool {
+        for segment in segments {
+            match segment {
+                IncomingSegment::Mention { user_id } if *user_id == self_id => return true,
+                IncomingSegment::Reply { message_seq } => {
+                    match self
+                        .client
+                        .get_message(MessageScene::Group, group_id, *message_seq)
+                        .await
+                    {
+                        Ok(reply) if reply.message.sender_id == self_id => return true,
+                        Ok(_) => {}
+                        Err(e) => warn!(
+                            "获取被回复的消息失败: 群ID: {}, message_seq: {}, error: {}",
+                            group_id, message_seq, e
+                        ),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        false
+    }
+
+    fn contains_keyword(&self, text_content: &str) -> bool {
+        self.keywords
+            .iter()
+            .any(|keyword| text_content.contains(keyword.as_str()))
+    }
 }
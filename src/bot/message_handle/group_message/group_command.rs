@@ -0,0 +1,227 @@
+use crate::agent::GROUP_TOOL_NAMES;
+use crate::db::group_model::{Group, GroupTriggerMode};
+use crate::db::group_service::GroupService;
+use crate::db::user_service::UserService;
+use crate::utils::send_group_message;
+use anyhow::{Result, bail};
+use milky_rust_sdk::MilkyClient;
+use milky_rust_sdk::prelude::{GroupMember, GroupRole};
+use std::sync::Arc;
+
+/// 群人设的最大字符数
+const MAX_GROUP_PERSONA_CHARS: usize = 500;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GroupCommand {
+    Show,
+    Enabled(bool),
+    Mode(String),
+    Persona(String),
+    Tools(String),
+    Rate(String),
+    Proactive(bool),
+    Unknown,
+}
+
+impl GroupCommand {
+    pub fn parse(input: &str) -> Self {
+        let args = input.strip_prefix("#group").unwrap_or_default().trim();
+        let (sub, value) = match args.split_once(char::is_whitespace) {
+            Some((sub, value)) => (sub, value.trim()),
+            None => (args, ""),
+        };
+
+        match (sub, value) {
+            ("", _) => GroupCommand::Show,
+            ("on", "") => GroupCommand::Enabled(true),
+            ("off", "") => GroupCommand::Enabled(false),
+            ("mode", mode) if !mode.is_empty() => GroupCommand::Mode(mode.to_string()),
+            ("persona", persona) if !persona.is_empty() => {
+                GroupCommand::Persona(persona.to_string())
+            }
+            ("tools", tools) if !tools.is_empty() => GroupCommand::Tools(tools.to_string()),
+            ("rate", rate) if !rate.is_empty() => GroupCommand::Rate(rate.to_string()),
+            ("proactive", "on") => GroupCommand::Proactive(true),
+            ("proactive", "off") => GroupCommand::Proactive(false),
+            _ => GroupCommand::Unknown,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct GroupCommandHandler {
+    user_service: UserService,
+    group_service: GroupService,
+    client: Arc<MilkyClient>,
+}
+
+impl GroupCommandHandler {
+    pub fn new(
+        user_service: UserService,
+        group_service: GroupService,
+        client: Arc<MilkyClient>,
+    ) -> Self {
+        Self {
+            user_service,
+            group_service,
+            client,
+        }
+    }
+
+    pub async fn handle(&self, group: &Group, member: &GroupMember, command: &str) -> Result<()> {
+        let result = self.process_command(group, member, command).await;
+
+        let message = match &result {
+            Ok(message) => message.clone(),
+            Err(e) => format!("命令执行失败: {}", e),
+        };
+        send_group_message(self.client.clone(), group.id, vec![message]).await;
+
+        result.map(|_| ())
+    }
+
+    async fn process_command(
+        &self,
+        group: &Group,
+        member: &GroupMember,
+        command: &str,
+    ) -> Result<String> {
+        let cmd = GroupCommand::parse(command);
+
+        let read_only = matches!(cmd, GroupCommand::Show | GroupCommand::Unknown);
+        let is_admin = matches!(member.role, GroupRole::Owner | GroupRole::Admin);
+        if !read_only && !is_admin && !self.user_service.is_master(member.user_id).await? {
+            bail!("只有群主、群管理员或 master 可以修改群配置");
+        }
+
+        let message = match cmd {
+            GroupCommand::Show => Self::describe(group),
+            GroupCommand::Unknown => Self::usage(),
+            GroupCommand::Enabled(enabled) => {
+                self.group_service.update_enabled(group.id, enabled).await?;
+                if enabled {
+                    "已在本群启用机器人".to_string()
+                } else {
+                    "已在本群停用机器人，使用 #group on 重新启用".to_string()
+                }
+            }
+            GroupCommand::Mode(mode) => {
+                let mode = GroupTriggerMode::from_str(&mode)?;
+                self.group_service
+                    .update_trigger_mode(group.id, mode)
+                    .await?;
+                format!("已设置触发方式: {}", Self::mode_label(mode))
+            }
+            GroupCommand::Persona(persona) => {
+                let persona = match persona.as_str() {
+                    "clear" => None,
+                    _ if persona.chars().count() > MAX_GROUP_PERSONA_CHARS => {
+                        bail!("人设不能超过 {} 个字符", MAX_GROUP_PERSONA_CHARS)
+                    }
+                    _ => Some(persona),
+                };
+                let group = self.group_service.update_persona(group.id, persona).await?;
+                match group.persona {
+                    Some(_) => "已设置本群人设".to_string(),
+                    None => "已清除本群人设".to_string(),
+                }
+            }
+            GroupCommand::Tools(tools) => {
+                let tools = match tools.as_str() {
+                    "all" => None,
+                    _ => {
+                        let tools: Vec<&str> = tools
+                            .split([',', '，', ' '])
+                            .map(|tool| tool.trim())
+                            .filter(|tool| !tool.is_empty())
+                            .collect();
+                        if let Some(unknown) =
+                            tools.iter().find(|tool| !GROUP_TOOL_NAMES.contains(tool))
+                        {
+                            bail!(
+                                "未知的工具 {}，可选 {}",
+                                unknown,
+                                GROUP_TOOL_NAMES.join(", ")
+                            );
+                        }
+                        Some(tools.join(","))
+                    }
+                };
+                let group = self
+                    .group_service
+                    .update_allowed_tools(group.id, tools)
+                    .await?;
+                format!(
+                    "已设置可用工具: {}",
+                    group.allowed_tools.as_deref().unwrap_or("全部")
+                )
+            }
+            GroupCommand::Rate(rate) => {
+                let rate = match rate.as_str() {
+                    "off" => None,
+                    rate => match rate.parse::<i64>() {
+                        Ok(rate) if rate > 0 => Some(rate),
+                        _ => bail!("回复频率需要是正整数，例如 #group rate 30"),
+                    },
+                };
+                self.group_service.update_rate_limit(group.id, rate).await?;
+                match rate {
+                    Some(rate) => format!("已设置每小时最多回复 {} 次", rate),
+                    None => "已取消回复频率限制".to_string(),
+                }
+            }
+            GroupCommand::Proactive(proactive) => {
+                self.group_service
+                    .update_proactive(group.id, proactive)
+                    .await?;
+                if proactive {
+                    "已开启主动发言，机器人会在合适的时候参与群聊".to_string()
+                } else {
+                    "已关闭主动发言".to_string()
+                }
+            }
+        };
+
+        Ok(message)
+    }
+
+    fn describe(group: &Group) -> String {
+        format!(
+            "群配置: {} ({})\n状态: {}\n触发方式: {}\n人设: {}\n可用工具: {}\n回复频率: {}\n主动发言: {}\n\n{}",
+            group.name,
+            group.id,
+            if group.enabled { "启用" } else { "停用" },
+            Self::mode_label(group.trigger_mode),
+            group.persona.as_deref().unwrap_or("无"),
+            group.allowed_tools.as_deref().unwrap_or("全部"),
+            group
+                .rate_limit
+                .map(|rate| format!("每小时 {} 次", rate))
+                .unwrap_or_else(|| "不限制".to_string()),
+            if group.proactive { "开启" } else { "关闭" },
+            Self::usage()
+        )
+    }
+
+    fn mode_label(mode: GroupTriggerMode) -> &'static str {
+        match mode {
+            GroupTriggerMode::Mention => "mention（仅 @ 或回复机器人）",
+            GroupTriggerMode::Keyword => "keyword（@、回复或包含关键词）",
+            GroupTriggerMode::All => "all（所有消息）",
+        }
+    }
+
+    fn usage() -> String {
+        [
+            "群配置命令（修改需要群主、群管理员或 master）:",
+            "#group - 查看本群配置",
+            "#group on|off - 启用或停用机器人",
+            "#group mode mention|keyword|all - 设置触发方式",
+            "#group persona [人设|clear] - 设置本群人设",
+            "#group tools [工具1,工具2|all] - 限制可用工具，可选 get_current_time, web_search, fetch_url",
+            "#group rate [次数|off] - 每小时最多回复次数",
+            "#group proactive on|off - 是否主动参与群聊",
+        ]
+        .join("\n")
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// 单条群消息保留的最大字符数，避免长消息占满上下文
+const HISTORY_MESSAGE_MAX_CHARS: usize = 200;
+
+/// 按群保存最近的消息，回复群消息时作为上下文提供给模型
+#[derive(Clone)]
+pub struct GroupHistory {
+    groups: Arc<Mutex<HashMap<i64, VecDeque<String>>>>,
+    size: usize,
+    max_chars: usize,
+}
+
+impl GroupHistory {
+    pub fn new(size: usize, max_chars: usize) -> Self {
+        Self {
+            groups: Arc::new(Mutex::new(HashMap::new())),
+            size,
+            max_chars,
+        }
+    }
+
+    pub fn push(&self, group_id: i64, name: &str, user_id: i64, text: &str) {
+        if self.size == 0 || text.is_empty() {
+            return;
+        }
+
+        let mut text: String = text.chars().take(HISTORY_MESSAGE_MAX_CHARS).collect();
+        if text.chars().count() == HISTORY_MESSAGE_MAX_CHARS {
+            text.push('…');
+        }
+
+        let mut groups = self.groups.lock().unwrap_or_else(|e| e.into_inner());
+        let messages = groups.entry(group_id).or_default();
+        messages.push_back(format!("{}({}): {}", name, user_id, text));
+        while messages.len() > self.size {
+            messages.pop_front();
+        }
+    }
+
+    /// 返回最近的消息，总字符数不超过上限，超出时丢弃较早的消息
+    pub fn recent(&self, group_id: i64) -> Vec<String> {
+        let groups = self.groups.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(messages) = groups.get(&group_id) else {
+            return Vec::new();
+        };
+
+        let mut total_chars = 0;
+        let mut recent: Vec<String> = messages
+            .iter()
+            .rev()
+            .take_while(|message| {
+                total_chars += message.chars().count();
+                total_chars <= self.max_chars
+            })
+            .cloned()
+            .collect();
+        recent.reverse();
+        recent
+    }
+}
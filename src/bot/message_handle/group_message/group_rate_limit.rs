@@ -0,0 +1,70 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// 主动发言时两次交给模型判断之间的最短间隔，避免热闹的群每条消息都调用模型
+const PROACTIVE_COOLDOWN: Duration = Duration::from_secs(10 * 60);
+
+/// 主动发言只考虑至少这么多字符的消息，表情和“哈哈”之类的短消息直接跳过
+const PROACTIVE_MIN_CHARS: usize = 8;
+
+/// 按群统计最近一小时机器人实际发出的回复次数
+#[derive(Clone, Default)]
+pub struct GroupRateLimit {
+    replies: Arc<Mutex<HashMap<i64, VecDeque<Instant>>>>,
+    proactive_checks: Arc<Mutex<HashMap<i64, Instant>>>,
+}
+
+impl GroupRateLimit {
+    /// 最近一小时的回复次数是否已达到上限
+    pub fn is_exhausted(&self, group_id: i64, limit: i64) -> bool {
+        let now = Instant::now();
+        let mut replies = self.replies.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(times) = replies.get_mut(&group_id) else {
+            return false;
+        };
+
+        while times
+            .front()
+            .is_some_and(|time| now.duration_since(*time) >= RATE_LIMIT_WINDOW)
+        {
+            times.pop_front();
+        }
+
+        times.len() as i64 >= limit
+    }
+
+    /// 机器人在群里发出回复后记录一次
+    pub fn record_reply(&self, group_id: i64) {
+        self.replies
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(group_id)
+            .or_default()
+            .push_back(Instant::now());
+    }
+
+    /// 未被叫到的消息是否值得交给模型判断要不要主动发言，通过时开始冷却
+    pub fn try_proactive(&self, group_id: i64, text: &str) -> bool {
+        if text.chars().count() < PROACTIVE_MIN_CHARS {
+            return false;
+        }
+
+        let now = Instant::now();
+        let mut checks = self
+            .proactive_checks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if checks
+            .get(&group_id)
+            .is_some_and(|last| now.duration_since(*last) < PROACTIVE_COOLDOWN)
+        {
+            return false;
+        }
+
+        checks.insert(group_id, now);
+        true
+    }
+}
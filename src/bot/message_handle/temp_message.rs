@@ -1,23 +1,106 @@
+use crate::agent::Agent;
+use crate::config::{BotConfig, TempPolicy};
+use crate::db::user_model::{CreateUserRequest, UserRelation};
+use crate::db::user_service::UserService;
+use crate::utils::send_message;
 use anyhow::Result;
 use milky_rust_sdk::MilkyClient;
 use milky_rust_sdk::prelude::TempMessage;
+use milky_rust_sdk::utils::get_plain_text_from_segments;
 use std::sync::Arc;
-use tracing::info;
+use tracing::{debug, error, info, warn};
 
+/// 临时会话消息的处理器
+///
+/// milky 没有单独的临时会话发送接口，协议端会把 send_private_message 发往非好友的消息
+/// 按临时会话投递，所以回复和好友消息一样走 send_message
 #[derive(Clone)]
 pub struct TempMessageHandler {
-    #[allow(dead_code)]
+    user_service: UserService,
     client: Arc<MilkyClient>,
+    agent: Arc<Agent>,
+    policy: TempPolicy,
+    auto_reply: String,
 }
 
 impl TempMessageHandler {
-    pub fn new(client: Arc<MilkyClient>) -> Self {
-        Self { client }
+    pub fn new(
+        user_service: UserService,
+        client: Arc<MilkyClient>,
+        agent: Arc<Agent>,
+        bot_config: &BotConfig,
+    ) -> Self {
+        Self {
+            user_service,
+            client,
+            agent,
+            policy: bot_config.temp_policy,
+            auto_reply: bot_config.temp_auto_reply.clone(),
+        }
     }
 
     pub async fn handle(&self, msg: TempMessage) -> Result<()> {
         let user_id = msg.message.sender_id;
-        info!("收到临时消息，用户ID: {}", user_id);
+        let group_id = msg.group.as_ref().map(|group| group.group_id);
+        info!("收到临时消息，用户ID: {}, 群ID: {:?}", user_id, group_id);
+
+        let text_content = get_plain_text_from_segments(&msg.message.segments);
+        let text_content = text_content.trim();
+        if text_content.is_empty() {
+            return Ok(());
+        }
+
+        let user = match self
+            .user_service
+            .create_stranger(CreateUserRequest {
+                id: user_id,
+                name: self.nickname_of(user_id).await,
+            })
+            .await
+        {
+            Ok(user) => user,
+            Err(e) => {
+                error!("创建用户失败: {e}");
+                return Err(e);
+            }
+        };
+
+        // 已经是 guest 或 master 的用户不受临时会话策略限制
+        let policy = match user.relation {
+            UserRelation::Stranger => self.policy,
+            UserRelation::Guest | UserRelation::Master => TempPolicy::Chat,
+        };
+
+        match policy {
+            TempPolicy::Ignore => {
+                debug!("临时会话策略为忽略，不回复: 用户ID: {}", user_id);
+            }
+            TempPolicy::AutoReply => {
+                send_message(self.client.clone(), user_id, vec![self.auto_reply.clone()]).await;
+            }
+            TempPolicy::Limited => {
+                self.agent.deal_limited(&user, text_content).await?;
+            }
+            TempPolicy::Chat => {
+                self.agent.deal(&user, text_content, None).await?;
+            }
+        }
+
         Ok(())
     }
+
+    /// 临时消息里没有发送者昵称，新用户需要单独查询资料
+    async fn nickname_of(&self, user_id: i64) -> String {
+        if let Ok(Some(user)) = self.user_service.get_user(user_id).await {
+            return user.name;
+        }
+
+        match self.client.get_user_profile(user_id).await {
+            Ok(profile) => profile.nickname,
+            Err(e) => {
+                warn!("获取用户资料失败: 用户ID: {}, error: {}", user_id, e);
+                user_id.to_string()
+            }
+        }
+    }
 }
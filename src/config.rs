@@ -8,6 +8,10 @@ pub struct Config {
     pub bot: BotConfig,
     pub llm: LLMConfig,
     pub database: DatabaseConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,8 +22,31 @@ pub struct BotConfig {
     pub event_channel_capacity: usize,
     #[serde(default = "default_max_concurrent_tasks")]
     pub max_concurrent_tasks: usize,
-    #[serde(default = "default_agent_task_channel_capacity")]
-    pub agent_task_channel_capacity: usize,
+    #[serde(default)]
+    pub group_keywords: Vec<String>,
+    #[serde(default = "default_group_history_size")]
+    pub group_history_size: usize,
+    #[serde(default = "default_group_history_max_chars")]
+    pub group_history_max_chars: usize,
+    #[serde(default)]
+    pub temp_policy: TempPolicy,
+    #[serde(default = "default_temp_auto_reply")]
+    pub temp_auto_reply: String,
+}
+
+/// 非好友通过群临时会话发来消息时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TempPolicy {
+    /// 不回复
+    #[default]
+    Ignore,
+    /// 回复固定的 temp_auto_reply
+    AutoReply,
+    /// 交给模型回复，但只能使用查询时间和发送消息的工具
+    Limited,
+    /// 和好友一样正常聊天
+    Chat,
 }
 
 fn default_event_channel_capacity() -> usize {
@@ -30,8 +57,16 @@ fn default_max_concurrent_tasks() -> usize {
     50
 }
 
-fn default_agent_task_channel_capacity() -> usize {
-    100
+fn default_group_history_size() -> usize {
+    20
+}
+
+fn default_group_history_max_chars() -> usize {
+    2000
+}
+
+fn default_temp_auto_reply() -> String {
+    "你好，我目前只和好友聊天，加个好友再来找我吧".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +96,190 @@ fn default_max_connections() -> u32 {
     5
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchProviderKind {
+    DuckDuckGo,
+    Searxng,
+    Bing,
+    Brave,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchConfig {
+    #[serde(default = "default_search_providers")]
+    pub providers: Vec<SearchProviderKind>,
+    #[serde(default = "default_search_max_results")]
+    pub max_results: usize,
+    #[serde(default = "default_search_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default)]
+    pub searxng_url: Option<String>,
+    #[serde(default)]
+    pub bing_api_key: Option<String>,
+    #[serde(default)]
+    pub brave_api_key: Option<String>,
+    #[serde(default = "default_search_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+    #[serde(default = "default_search_cache_max_entries")]
+    pub cache_max_entries: usize,
+}
+
+fn default_search_providers() -> Vec<SearchProviderKind> {
+    vec![SearchProviderKind::DuckDuckGo]
+}
+
+fn default_search_max_results() -> usize {
+    5
+}
+
+fn default_search_timeout_secs() -> u64 {
+    10
+}
+
+fn default_search_cache_ttl_secs() -> u64 {
+    600
+}
+
+fn default_search_cache_max_entries() -> usize {
+    200
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            providers: default_search_providers(),
+            max_results: default_search_max_results(),
+            timeout_secs: default_search_timeout_secs(),
+            searxng_url: None,
+            bing_api_key: None,
+            brave_api_key: None,
+            cache_ttl_secs: default_search_cache_ttl_secs(),
+            cache_max_entries: default_search_cache_max_entries(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerConfig {
+    #[serde(default = "default_timezone")]
+    pub default_timezone: String,
+    #[serde(default = "default_misfire_grace_secs")]
+    pub misfire_grace_secs: u64,
+    #[serde(default = "default_greetings_path")]
+    pub greetings_path: String,
+    #[serde(default = "default_holidays_path")]
+    pub holidays_path: String,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_retry_delay_secs")]
+    pub retry_delay_secs: u64,
+    #[serde(default = "default_reminder_reply_window_secs")]
+    pub reminder_reply_window_secs: u64,
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+    #[serde(default = "default_checkin_inactive_days")]
+    pub checkin_inactive_days: u32,
+    #[serde(default = "default_checkin_cooldown_days")]
+    pub checkin_cooldown_days: u32,
+    #[serde(default = "default_checkin_max_unanswered")]
+    pub checkin_max_unanswered: u32,
+    #[serde(default)]
+    pub task_quota: TaskQuotaConfig,
+}
+
+/// 每个用户可同时启用的定时任务数量上限，0 表示不限制
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskQuotaConfig {
+    #[serde(default)]
+    pub master: u32,
+    #[serde(default = "default_guest_task_quota")]
+    pub guest: u32,
+    #[serde(default = "default_stranger_task_quota")]
+    pub stranger: u32,
+}
+
+fn default_guest_task_quota() -> u32 {
+    20
+}
+
+fn default_stranger_task_quota() -> u32 {
+    3
+}
+
+impl Default for TaskQuotaConfig {
+    fn default() -> Self {
+        Self {
+            master: 0,
+            guest: default_guest_task_quota(),
+            stranger: default_stranger_task_quota(),
+        }
+    }
+}
+
+fn default_timezone() -> String {
+    "Asia/Shanghai".to_string()
+}
+
+fn default_misfire_grace_secs() -> u64 {
+    3600
+}
+
+fn default_greetings_path() -> String {
+    "greetings.toml".to_string()
+}
+
+fn default_holidays_path() -> String {
+    "holidays.toml".to_string()
+}
+
+fn default_max_retries() -> u32 {
+    2
+}
+
+fn default_retry_delay_secs() -> u64 {
+    60
+}
+
+fn default_reminder_reply_window_secs() -> u64 {
+    600
+}
+
+fn default_workers() -> usize {
+    4
+}
+
+fn default_checkin_inactive_days() -> u32 {
+    3
+}
+
+fn default_checkin_cooldown_days() -> u32 {
+    3
+}
+
+fn default_checkin_max_unanswered() -> u32 {
+    2
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            default_timezone: default_timezone(),
+            misfire_grace_secs: default_misfire_grace_secs(),
+            greetings_path: default_greetings_path(),
+            holidays_path: default_holidays_path(),
+            max_retries: default_max_retries(),
+            retry_delay_secs: default_retry_delay_secs(),
+            reminder_reply_window_secs: default_reminder_reply_window_secs(),
+            workers: default_workers(),
+            checkin_inactive_days: default_checkin_inactive_days(),
+            checkin_cooldown_days: default_checkin_cooldown_days(),
+            checkin_max_unanswered: default_checkin_max_unanswered(),
+            task_quota: TaskQuotaConfig::default(),
+        }
+    }
+}
+
 impl Config {
     fn default() -> Self {
         Self {
@@ -69,7 +288,11 @@ impl Config {
                 access_token: "your-access-token".to_string(),
                 event_channel_capacity: default_event_channel_capacity(),
                 max_concurrent_tasks: default_max_concurrent_tasks(),
-                agent_task_channel_capacity: default_agent_task_channel_capacity(),
+                group_keywords: Vec::new(),
+                group_history_size: default_group_history_size(),
+                group_history_max_chars: default_group_history_max_chars(),
+                temp_policy: TempPolicy::default(),
+                temp_auto_reply: default_temp_auto_reply(),
             },
             llm: LLMConfig {
                 base_url: "your-model-base-url".to_string(),
@@ -82,6 +305,8 @@ impl Config {
                 url: "sqlite://data.db".to_string(),
                 max_connections: default_max_connections(),
             },
+            search: SearchConfig::default(),
+            scheduler: SchedulerConfig::default(),
         }
     }
 
@@ -1,5 +1,15 @@
+pub mod agent_job_model;
+pub mod agent_job_service;
+pub mod group_model;
+pub mod group_service;
+pub mod reminder_message_model;
+pub mod reminder_message_service;
 pub mod scheduler_model;
 pub mod scheduler_service;
+pub mod search_cache_model;
+pub mod search_cache_service;
+pub mod task_run_model;
+pub mod task_run_service;
 pub mod user_model;
 pub mod user_service;
 
@@ -9,6 +19,34 @@ use sqlx::{
     sqlite::{SqliteConnectOptions, SqlitePoolOptions},
 };
 use std::str::FromStr;
+use tracing::info;
+
+const SCHEDULED_TASKS_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS scheduled_tasks (
+        id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+        frequency TEXT NOT NULL CHECK(frequency IN ('once', 'daily', 'weekly', 'monthly', 'weekdays', 'cron')),
+        cron_expr TEXT NOT NULL,
+        target_user_id INTEGER NOT NULL,
+        content TEXT NOT NULL,
+        context TEXT,
+        created_by TEXT NOT NULL DEFAULT 'user' CHECK(created_by IN ('system', 'user')),
+        creator_user_id INTEGER,
+        enabled INTEGER NOT NULL DEFAULT 1,
+        start_at DATETIME,
+        end_at DATETIME,
+        max_runs INTEGER,
+        run_count INTEGER NOT NULL DEFAULT 0,
+        misfire_policy TEXT NOT NULL DEFAULT 'run_immediately' CHECK(misfire_policy IN ('run_immediately', 'skip', 'expire')),
+        max_retries INTEGER,
+        urgent INTEGER NOT NULL DEFAULT 0,
+        calendar TEXT NOT NULL DEFAULT 'any' CHECK(calendar IN ('any', 'cn_workday')),
+        last_run_at DATETIME,
+        next_run_at DATETIME,
+        created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (target_user_id) REFERENCES users(id)
+    )
+"#;
 
 pub async fn init_db(database_url: &str, max_connections: u32) -> Result<SqlitePool> {
     let options = SqliteConnectOptions::from_str(database_url)?.create_if_missing(true);
@@ -35,6 +73,16 @@ pub async fn init_db(database_url: &str, max_connections: u32) -> Result<SqliteP
             name TEXT NOT NULL,
             relation TEXT NOT NULL DEFAULT 'guest' CHECK(relation IN ('master', 'guest', 'stranger')),
             custom_prompt TEXT,
+            timezone TEXT,
+            greetings_enabled INTEGER NOT NULL DEFAULT 1,
+            greeting_categories TEXT,
+            quiet_start TEXT,
+            quiet_end TEXT,
+            dnd_until DATETIME,
+            last_active_at DATETIME,
+            last_message TEXT,
+            last_checkin_at DATETIME,
+            checkin_count INTEGER NOT NULL DEFAULT 0,
             created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
             updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
         )
@@ -43,6 +91,29 @@ pub async fn init_db(database_url: &str, max_connections: u32) -> Result<SqliteP
         .execute(&pool)
         .await?;
 
+    ensure_column(&pool, "users", "timezone", "TEXT").await?;
+    ensure_column(
+        &pool,
+        "users",
+        "greetings_enabled",
+        "INTEGER NOT NULL DEFAULT 1",
+    )
+    .await?;
+    ensure_column(&pool, "users", "greeting_categories", "TEXT").await?;
+    ensure_column(&pool, "users", "quiet_start", "TEXT").await?;
+    ensure_column(&pool, "users", "quiet_end", "TEXT").await?;
+    ensure_column(&pool, "users", "dnd_until", "DATETIME").await?;
+    ensure_column(&pool, "users", "last_active_at", "DATETIME").await?;
+    ensure_column(&pool, "users", "last_message", "TEXT").await?;
+    ensure_column(&pool, "users", "last_checkin_at", "DATETIME").await?;
+    ensure_column(
+        &pool,
+        "users",
+        "checkin_count",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+    .await?;
+
     sqlx::query(
         r#"
         CREATE TRIGGER IF NOT EXISTS update_users_timestamp
@@ -58,25 +129,84 @@ pub async fn init_db(database_url: &str, max_connections: u32) -> Result<SqliteP
 
     sqlx::query(
         r#"
-        CREATE TABLE IF NOT EXISTS scheduled_tasks (
-            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
-            frequency TEXT NOT NULL CHECK(frequency IN ('once', 'daily')),
-            cron_expr TEXT NOT NULL,
-            target_user_id INTEGER NOT NULL,
-            content TEXT NOT NULL,
-            created_by TEXT NOT NULL DEFAULT 'user' CHECK(created_by IN ('system', 'user')),
+        CREATE TABLE IF NOT EXISTS groups (
+            id INTEGER PRIMARY KEY NOT NULL,
+            name TEXT NOT NULL,
             enabled INTEGER NOT NULL DEFAULT 1,
-            last_run_at DATETIME,
-            next_run_at DATETIME,
+            trigger_mode TEXT NOT NULL DEFAULT 'keyword' CHECK(trigger_mode IN ('mention', 'keyword', 'all')),
+            persona TEXT,
+            allowed_tools TEXT,
+            rate_limit INTEGER,
+            proactive INTEGER NOT NULL DEFAULT 0,
             created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            FOREIGN KEY (target_user_id) REFERENCES users(id)
+            updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
         )
         "#,
     )
     .execute(&pool)
     .await?;
 
+    sqlx::query(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS update_groups_timestamp
+        AFTER UPDATE ON groups
+        FOR EACH ROW
+        BEGIN
+            UPDATE groups SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+        END
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(SCHEDULED_TASKS_SCHEMA).execute(&pool).await?;
+
+    rebuild_outdated_scheduled_tasks(&pool).await?;
+    ensure_column(&pool, "scheduled_tasks", "start_at", "DATETIME").await?;
+    ensure_column(&pool, "scheduled_tasks", "end_at", "DATETIME").await?;
+    ensure_column(&pool, "scheduled_tasks", "max_runs", "INTEGER").await?;
+    ensure_column(
+        &pool,
+        "scheduled_tasks",
+        "run_count",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+    .await?;
+    ensure_column(
+        &pool,
+        "scheduled_tasks",
+        "misfire_policy",
+        "TEXT NOT NULL DEFAULT 'run_immediately' CHECK(misfire_policy IN ('run_immediately', 'skip', 'expire'))",
+    )
+    .await?;
+    ensure_column(&pool, "scheduled_tasks", "max_retries", "INTEGER").await?;
+    ensure_column(
+        &pool,
+        "scheduled_tasks",
+        "urgent",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+    .await?;
+    ensure_column(&pool, "scheduled_tasks", "creator_user_id", "INTEGER").await?;
+    ensure_column(
+        &pool,
+        "scheduled_tasks",
+        "calendar",
+        "TEXT NOT NULL DEFAULT 'any' CHECK(calendar IN ('any', 'cn_workday'))",
+    )
+    .await?;
+    ensure_column(&pool, "scheduled_tasks", "context", "TEXT").await?;
+
+    // 旧版本的用户任务都由目标用户自己创建
+    sqlx::query(
+        r#"
+        UPDATE scheduled_tasks SET creator_user_id = target_user_id
+        WHERE creator_user_id IS NULL AND created_by = 'user'
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
     sqlx::query(
         r#"
         CREATE TRIGGER IF NOT EXISTS update_scheduled_tasks_timestamp
@@ -90,5 +220,161 @@ pub async fn init_db(database_url: &str, max_connections: u32) -> Result<SqliteP
     .execute(&pool)
     .await?;
 
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS search_cache (
+            query_key TEXT PRIMARY KEY NOT NULL,
+            provider TEXT NOT NULL,
+            results TEXT NOT NULL,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS task_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            task_id INTEGER,
+            target_user_id INTEGER NOT NULL,
+            attempt INTEGER NOT NULL DEFAULT 0,
+            status TEXT NOT NULL,
+            error TEXT,
+            scheduled_at DATETIME NOT NULL,
+            started_at DATETIME NOT NULL,
+            latency_ms INTEGER NOT NULL,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS agent_jobs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            idempotency_key TEXT NOT NULL UNIQUE,
+            task_id INTEGER,
+            target_user_id INTEGER NOT NULL,
+            content TEXT NOT NULL,
+            scheduled_at DATETIME NOT NULL,
+            attempt INTEGER NOT NULL DEFAULT 0,
+            status TEXT NOT NULL DEFAULT 'pending' CHECK(status IN ('pending', 'running', 'done', 'failed')),
+            error TEXT,
+            available_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_agent_jobs_status ON agent_jobs (status, available_at)",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS reminder_messages (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            message_seq INTEGER NOT NULL,
+            user_id INTEGER NOT NULL,
+            task_id INTEGER NOT NULL,
+            ack TEXT CHECK(ack IN ('snoozed', 'done', 'cancelled')),
+            sent_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (user_id, message_seq)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS update_agent_jobs_timestamp
+        AFTER UPDATE ON agent_jobs
+        FOR EACH ROW
+        BEGIN
+            UPDATE agent_jobs SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+        END
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
     Ok(pool)
 }
+
+async fn rebuild_outdated_scheduled_tasks(pool: &SqlitePool) -> Result<()> {
+    let schema = sqlx::query_scalar::<_, String>(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'scheduled_tasks'",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if !schema.contains("CHECK(frequency IN ('once', 'daily'))") {
+        return Ok(());
+    }
+
+    info!("迁移 scheduled_tasks 表以支持新的任务频率");
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("ALTER TABLE scheduled_tasks RENAME TO scheduled_tasks_old")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(SCHEDULED_TASKS_SCHEMA)
+        .execute(&mut *tx)
+        .await?;
+
+    let columns = sqlx::query_scalar::<_, String>(
+        "SELECT name FROM pragma_table_info('scheduled_tasks_old')",
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .join(", ");
+
+    sqlx::query(&format!(
+        "INSERT INTO scheduled_tasks ({columns}) SELECT {columns} FROM scheduled_tasks_old"
+    ))
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DROP TABLE scheduled_tasks_old")
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn ensure_column(
+    pool: &SqlitePool,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let exists = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM pragma_table_info(?) WHERE name = ?)",
+    )
+    .bind(table)
+    .bind(column)
+    .fetch_one(pool)
+    .await?;
+
+    if !exists {
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
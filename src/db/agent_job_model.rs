@@ -0,0 +1,65 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentJobStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+impl AgentJobStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AgentJobStatus::Pending => "pending",
+            AgentJobStatus::Running => "running",
+            AgentJobStatus::Done => "done",
+            AgentJobStatus::Failed => "failed",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pending" => Ok(AgentJobStatus::Pending),
+            "running" => Ok(AgentJobStatus::Running),
+            "done" => Ok(AgentJobStatus::Done),
+            "failed" => Ok(AgentJobStatus::Failed),
+            _ => Err(anyhow!("无效的队列任务状态: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentJob {
+    pub id: i64,
+    pub idempotency_key: String,
+    pub task_id: Option<i64>,
+    pub target_user_id: i64,
+    pub content: String,
+    pub scheduled_at: String,
+    pub attempt: i64,
+    pub status: AgentJobStatus,
+    pub error: Option<String>,
+    pub available_at: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateAgentJobRequest {
+    pub idempotency_key: String,
+    pub task_id: Option<i64>,
+    pub target_user_id: i64,
+    pub content: String,
+    pub scheduled_at: String,
+    pub attempt: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RescheduleAgentJobRequest {
+    pub id: i64,
+    pub attempt: i64,
+    pub available_at: String,
+}
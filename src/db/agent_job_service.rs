@@ -0,0 +1,195 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use tracing::debug;
+
+use super::agent_job_model::{
+    AgentJob, AgentJobStatus, CreateAgentJobRequest, RescheduleAgentJobRequest,
+};
+
+const AGENT_JOB_COLUMNS: &str = r#"
+    id, idempotency_key, task_id, target_user_id, content, scheduled_at, attempt,
+    status, error, available_at, created_at, updated_at
+"#;
+
+#[derive(sqlx::FromRow)]
+struct AgentJobRow {
+    id: i64,
+    idempotency_key: String,
+    task_id: Option<i64>,
+    target_user_id: i64,
+    content: String,
+    scheduled_at: String,
+    attempt: i64,
+    status: String,
+    error: Option<String>,
+    available_at: String,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Clone)]
+pub struct AgentJobService {
+    pool: SqlitePool,
+}
+
+impl AgentJobService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// 写入队列，幂等键已存在时不重复写入并返回 false
+    pub async fn enqueue(&self, req: CreateAgentJobRequest) -> Result<bool> {
+        debug!(
+            "写入任务队列: key={}, task_id={:?}, user_id={}",
+            req.idempotency_key, req.task_id, req.target_user_id
+        );
+
+        let rows_affected = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO agent_jobs
+                (idempotency_key, task_id, target_user_id, content, scheduled_at, attempt)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&req.idempotency_key)
+        .bind(req.task_id)
+        .bind(req.target_user_id)
+        .bind(&req.content)
+        .bind(&req.scheduled_at)
+        .bind(req.attempt)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            debug!("任务已在队列中，忽略重复写入: key={}", req.idempotency_key);
+        }
+
+        Ok(rows_affected > 0)
+    }
+
+    /// 取出一个已到期的待执行任务并标记为执行中，同一用户同时只会有一个任务在执行
+    pub async fn claim_next(&self, now: &str) -> Result<Option<AgentJob>> {
+        let row = sqlx::query_as::<_, AgentJobRow>(&format!(
+            r#"
+            UPDATE agent_jobs
+            SET status = 'running'
+            WHERE id = (
+                SELECT id FROM agent_jobs
+                WHERE status = 'pending'
+                  AND available_at <= ?
+                  AND target_user_id NOT IN (
+                      SELECT target_user_id FROM agent_jobs WHERE status = 'running'
+                  )
+                ORDER BY available_at, id
+                LIMIT 1
+            )
+            RETURNING {AGENT_JOB_COLUMNS}
+            "#
+        ))
+        .bind(now)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(Self::map_row_to_job).transpose()
+    }
+
+    pub async fn count_due(&self, now: &str) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM agent_jobs WHERE status = 'pending' AND available_at <= ?",
+        )
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    pub async fn next_available_at(&self) -> Result<Option<String>> {
+        let available_at = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT MIN(available_at) FROM agent_jobs WHERE status = 'pending'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(available_at)
+    }
+
+    pub async fn finish(&self, id: i64, status: AgentJobStatus, error: Option<&str>) -> Result<()> {
+        debug!("队列任务结束: id={}, status={}", id, status.as_str());
+
+        sqlx::query("UPDATE agent_jobs SET status = ?, error = ? WHERE id = ?")
+            .bind(status.as_str())
+            .bind(error)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 将任务放回队列，在 available_at 之后再次执行
+    pub async fn reschedule(&self, req: RescheduleAgentJobRequest) -> Result<()> {
+        debug!(
+            "队列任务重新排队: id={}, attempt={}, available_at={}",
+            req.id, req.attempt, req.available_at
+        );
+
+        sqlx::query(
+            r#"
+            UPDATE agent_jobs
+            SET status = 'pending', attempt = ?, available_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(req.attempt)
+        .bind(&req.available_at)
+        .bind(req.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 将上次进程退出时仍在执行中的任务恢复为待执行
+    pub async fn recover_running(&self) -> Result<u64> {
+        let rows_affected =
+            sqlx::query("UPDATE agent_jobs SET status = 'pending' WHERE status = 'running'")
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+
+        debug!("恢复执行中的队列任务: {} 个", rows_affected);
+        Ok(rows_affected)
+    }
+
+    pub async fn delete_finished_before(&self, before: &str) -> Result<u64> {
+        let rows_affected = sqlx::query(
+            "DELETE FROM agent_jobs WHERE status IN ('done', 'failed') AND updated_at < ?",
+        )
+        .bind(before)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        debug!("清理已结束的队列任务: {} 个", rows_affected);
+        Ok(rows_affected)
+    }
+
+    fn map_row_to_job(row: AgentJobRow) -> Result<AgentJob> {
+        Ok(AgentJob {
+            id: row.id,
+            idempotency_key: row.idempotency_key,
+            task_id: row.task_id,
+            target_user_id: row.target_user_id,
+            content: row.content,
+            scheduled_at: row.scheduled_at,
+            attempt: row.attempt,
+            status: AgentJobStatus::from_str(&row.status)?,
+            error: row.error,
+            available_at: row.available_at,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupTriggerMode {
+    /// 只响应 @机器人 或回复机器人的消息
+    Mention,
+    /// 在 Mention 的基础上，包含关键词的消息也会触发
+    Keyword,
+    /// 响应群里的所有消息
+    All,
+}
+
+impl GroupTriggerMode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            GroupTriggerMode::Mention => "mention",
+            GroupTriggerMode::Keyword => "keyword",
+            GroupTriggerMode::All => "all",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "mention" => Ok(GroupTriggerMode::Mention),
+            "keyword" => Ok(GroupTriggerMode::Keyword),
+            "all" => Ok(GroupTriggerMode::All),
+            _ => Err(anyhow!("无效的群触发方式: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Group {
+    pub id: i64,
+    pub name: String,
+    pub enabled: bool,
+    pub trigger_mode: GroupTriggerMode,
+    pub persona: Option<String>,
+    /// 允许使用的工具，逗号分隔，为空表示不限制
+    pub allowed_tools: Option<String>,
+    /// 每小时最多回复的次数，为空表示不限制
+    pub rate_limit: Option<i64>,
+    pub proactive: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Group {
+    pub fn allowed_tools(&self) -> Option<Vec<String>> {
+        self.allowed_tools.as_deref().map(|tools| {
+            tools
+                .split(',')
+                .map(|tool| tool.trim().to_string())
+                .filter(|tool| !tool.is_empty())
+                .collect()
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateGroupRequest {
+    pub id: i64,
+    pub name: String,
+}
@@ -0,0 +1,167 @@
+use anyhow::{Result, anyhow};
+use sqlx::SqlitePool;
+use tracing::debug;
+
+use super::group_model::{CreateGroupRequest, Group, GroupTriggerMode};
+
+const GROUP_COLUMNS: &str = r#"
+    id, name, enabled, trigger_mode, persona, allowed_tools, rate_limit, proactive,
+    created_at, updated_at
+"#;
+
+#[derive(sqlx::FromRow)]
+struct GroupRow {
+    id: i64,
+    name: String,
+    enabled: bool,
+    trigger_mode: String,
+    persona: Option<String>,
+    allowed_tools: Option<String>,
+    rate_limit: Option<i64>,
+    proactive: bool,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Clone)]
+pub struct GroupService {
+    pool: SqlitePool,
+}
+
+impl GroupService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// 群第一次出现时按默认配置创建，群名变化时同步更新
+    pub async fn create_group(&self, req: CreateGroupRequest) -> Result<Group> {
+        debug!("创建群配置请求: id={}, name={}", req.id, req.name);
+
+        sqlx::query(
+            r#"
+            INSERT INTO groups (id, name)
+            VALUES (?, ?)
+            ON CONFLICT(id) DO UPDATE SET name = excluded.name
+            WHERE name != excluded.name
+            "#,
+        )
+        .bind(req.id)
+        .bind(&req.name)
+        .execute(&self.pool)
+        .await?;
+
+        self.get_group(req.id)
+            .await?
+            .ok_or_else(|| anyhow!("创建群配置后无法查询到群"))
+    }
+
+    pub async fn update_enabled(&self, id: i64, enabled: bool) -> Result<Group> {
+        debug!("更新群启用状态: id={}, enabled={}", id, enabled);
+
+        sqlx::query("UPDATE groups SET enabled = ? WHERE id = ?")
+            .bind(enabled)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        self.require_group(id).await
+    }
+
+    pub async fn update_trigger_mode(&self, id: i64, mode: GroupTriggerMode) -> Result<Group> {
+        debug!("更新群触发方式: id={}, mode={}", id, mode.as_str());
+
+        sqlx::query("UPDATE groups SET trigger_mode = ? WHERE id = ?")
+            .bind(mode.as_str())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        self.require_group(id).await
+    }
+
+    pub async fn update_persona(&self, id: i64, persona: Option<String>) -> Result<Group> {
+        debug!("更新群人设: id={}, persona={:?}", id, persona);
+
+        sqlx::query("UPDATE groups SET persona = ? WHERE id = ?")
+            .bind(&persona)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        self.require_group(id).await
+    }
+
+    pub async fn update_allowed_tools(&self, id: i64, tools: Option<String>) -> Result<Group> {
+        debug!("更新群可用工具: id={}, tools={:?}", id, tools);
+
+        sqlx::query("UPDATE groups SET allowed_tools = ? WHERE id = ?")
+            .bind(&tools)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        self.require_group(id).await
+    }
+
+    pub async fn update_rate_limit(&self, id: i64, rate_limit: Option<i64>) -> Result<Group> {
+        debug!("更新群回复频率: id={}, rate_limit={:?}", id, rate_limit);
+
+        sqlx::query("UPDATE groups SET rate_limit = ? WHERE id = ?")
+            .bind(rate_limit)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        self.require_group(id).await
+    }
+
+    pub async fn update_proactive(&self, id: i64, proactive: bool) -> Result<Group> {
+        debug!("更新群主动发言: id={}, proactive={}", id, proactive);
+
+        sqlx::query("UPDATE groups SET proactive = ? WHERE id = ?")
+            .bind(proactive)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        self.require_group(id).await
+    }
+
+    pub async fn get_group(&self, id: i64) -> Result<Option<Group>> {
+        debug!("查询群配置: id={}", id);
+
+        let row = sqlx::query_as::<_, GroupRow>(&format!(
+            r#"
+            SELECT {GROUP_COLUMNS}
+            FROM groups
+            WHERE id = ?
+            "#
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(Self::map_row_to_group).transpose()
+    }
+
+    async fn require_group(&self, id: i64) -> Result<Group> {
+        self.get_group(id)
+            .await?
+            .ok_or_else(|| anyhow!("群 {} 不存在", id))
+    }
+
+    fn map_row_to_group(row: GroupRow) -> Result<Group> {
+        Ok(Group {
+            id: row.id,
+            name: row.name,
+            enabled: row.enabled,
+            trigger_mode: GroupTriggerMode::from_str(&row.trigger_mode)?,
+            persona: row.persona,
+            allowed_tools: row.allowed_tools,
+            rate_limit: row.rate_limit,
+            proactive: row.proactive,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
@@ -0,0 +1,46 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReminderAck {
+    Snoozed,
+    Done,
+    Cancelled,
+}
+
+impl ReminderAck {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ReminderAck::Snoozed => "snoozed",
+            ReminderAck::Done => "done",
+            ReminderAck::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "snoozed" => Ok(ReminderAck::Snoozed),
+            "done" => Ok(ReminderAck::Done),
+            "cancelled" => Ok(ReminderAck::Cancelled),
+            _ => Err(anyhow!("无效的提醒回复类型: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReminderMessage {
+    pub id: i64,
+    pub message_seq: i64,
+    pub user_id: i64,
+    pub task_id: i64,
+    pub ack: Option<ReminderAck>,
+    pub sent_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateReminderMessagesRequest {
+    pub user_id: i64,
+    pub task_id: i64,
+    pub message_seqs: Vec<i64>,
+}
@@ -0,0 +1,139 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use tracing::debug;
+
+use super::reminder_message_model::{CreateReminderMessagesRequest, ReminderAck, ReminderMessage};
+
+const REMINDER_MESSAGE_COLUMNS: &str = "id, message_seq, user_id, task_id, ack, sent_at";
+
+#[derive(sqlx::FromRow)]
+struct ReminderMessageRow {
+    id: i64,
+    message_seq: i64,
+    user_id: i64,
+    task_id: i64,
+    ack: Option<String>,
+    sent_at: String,
+}
+
+#[derive(Clone)]
+pub struct ReminderMessageService {
+    pool: SqlitePool,
+}
+
+impl ReminderMessageService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create_messages(&self, req: CreateReminderMessagesRequest) -> Result<()> {
+        debug!(
+            "记录提醒消息: user_id={}, task_id={}, message_seqs={:?}",
+            req.user_id, req.task_id, req.message_seqs
+        );
+
+        for message_seq in &req.message_seqs {
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO reminder_messages (message_seq, user_id, task_id)
+                VALUES (?, ?, ?)
+                "#,
+            )
+            .bind(message_seq)
+            .bind(req.user_id)
+            .bind(req.task_id)
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_by_seq(
+        &self,
+        user_id: i64,
+        message_seq: i64,
+    ) -> Result<Option<ReminderMessage>> {
+        debug!(
+            "按消息序列号查询提醒消息: user_id={}, message_seq={}",
+            user_id, message_seq
+        );
+
+        let row = sqlx::query_as::<_, ReminderMessageRow>(&format!(
+            r#"
+            SELECT {REMINDER_MESSAGE_COLUMNS}
+            FROM reminder_messages
+            WHERE user_id = ? AND message_seq = ?
+            "#
+        ))
+        .bind(user_id)
+        .bind(message_seq)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(Self::map_row_to_message).transpose()
+    }
+
+    /// 查询用户在 since 之后收到的最近一条尚未回复的提醒
+    pub async fn get_latest_pending(
+        &self,
+        user_id: i64,
+        since: &str,
+    ) -> Result<Option<ReminderMessage>> {
+        debug!(
+            "查询最近未回复的提醒消息: user_id={}, since={}",
+            user_id, since
+        );
+
+        let row = sqlx::query_as::<_, ReminderMessageRow>(&format!(
+            r#"
+            SELECT {REMINDER_MESSAGE_COLUMNS}
+            FROM reminder_messages
+            WHERE user_id = ? AND ack IS NULL AND sent_at >= ?
+            ORDER BY id DESC
+            LIMIT 1
+            "#
+        ))
+        .bind(user_id)
+        .bind(since)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(Self::map_row_to_message).transpose()
+    }
+
+    /// 标记同一任务发给用户的所有未回复提醒
+    pub async fn acknowledge(&self, user_id: i64, task_id: i64, ack: ReminderAck) -> Result<()> {
+        debug!(
+            "标记提醒已回复: user_id={}, task_id={}, ack={}",
+            user_id,
+            task_id,
+            ack.as_str()
+        );
+
+        sqlx::query(
+            r#"
+            UPDATE reminder_messages SET ack = ?
+            WHERE user_id = ? AND task_id = ? AND ack IS NULL
+            "#,
+        )
+        .bind(ack.as_str())
+        .bind(user_id)
+        .bind(task_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    fn map_row_to_message(row: ReminderMessageRow) -> Result<ReminderMessage> {
+        Ok(ReminderMessage {
+            id: row.id,
+            message_seq: row.message_seq,
+            user_id: row.user_id,
+            task_id: row.task_id,
+            ack: row.ack.as_deref().map(ReminderAck::from_str).transpose()?,
+            sent_at: row.sent_at,
+        })
+    }
+}
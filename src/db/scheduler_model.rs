@@ -6,6 +6,10 @@ use serde::{Deserialize, Serialize};
 pub enum TaskFrequency {
     Once,
     Daily,
+    Weekly,
+    Monthly,
+    Weekdays,
+    Cron,
 }
 
 impl TaskFrequency {
@@ -13,6 +17,10 @@ impl TaskFrequency {
         match self {
             TaskFrequency::Once => "once",
             TaskFrequency::Daily => "daily",
+            TaskFrequency::Weekly => "weekly",
+            TaskFrequency::Monthly => "monthly",
+            TaskFrequency::Weekdays => "weekdays",
+            TaskFrequency::Cron => "cron",
         }
     }
 
@@ -20,6 +28,10 @@ impl TaskFrequency {
         match s {
             "once" => Ok(TaskFrequency::Once),
             "daily" => Ok(TaskFrequency::Daily),
+            "weekly" => Ok(TaskFrequency::Weekly),
+            "monthly" => Ok(TaskFrequency::Monthly),
+            "weekdays" => Ok(TaskFrequency::Weekdays),
+            "cron" => Ok(TaskFrequency::Cron),
             _ => Err(anyhow!("无效的任务频率: {}", s)),
         }
     }
@@ -49,6 +61,58 @@ impl TaskCreator {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MisfirePolicy {
+    RunImmediately,
+    Skip,
+    Expire,
+}
+
+impl MisfirePolicy {
+    pub fn as_str(&self) -> &str {
+        match self {
+            MisfirePolicy::RunImmediately => "run_immediately",
+            MisfirePolicy::Skip => "skip",
+            MisfirePolicy::Expire => "expire",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "run_immediately" => Ok(MisfirePolicy::RunImmediately),
+            "skip" => Ok(MisfirePolicy::Skip),
+            "expire" => Ok(MisfirePolicy::Expire),
+            _ => Err(anyhow!("无效的错过执行策略: {}", s)),
+        }
+    }
+}
+
+/// 任务执行日历，cn_workday 跳过法定节假日并在调休上班日照常执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskCalendar {
+    Any,
+    CnWorkday,
+}
+
+impl TaskCalendar {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TaskCalendar::Any => "any",
+            TaskCalendar::CnWorkday => "cn_workday",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "any" => Ok(TaskCalendar::Any),
+            "cn_workday" => Ok(TaskCalendar::CnWorkday),
+            _ => Err(anyhow!("无效的任务日历: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScheduledTask {
     pub id: i64,
@@ -56,8 +120,18 @@ pub struct ScheduledTask {
     pub cron_expr: String,
     pub target_user_id: i64,
     pub content: String,
+    pub context: Option<String>,
     pub created_by: TaskCreator,
+    pub creator_user_id: Option<i64>,
     pub enabled: bool,
+    pub start_at: Option<String>,
+    pub end_at: Option<String>,
+    pub max_runs: Option<i64>,
+    pub run_count: i64,
+    pub misfire_policy: MisfirePolicy,
+    pub max_retries: Option<i64>,
+    pub urgent: bool,
+    pub calendar: TaskCalendar,
     pub last_run_at: Option<String>,
     pub next_run_at: Option<String>,
     pub created_at: String,
@@ -70,5 +144,30 @@ pub struct CreateTaskRequest {
     pub frequency: TaskFrequency,
     pub cron_expr: String,
     pub content: String,
+    pub context: Option<String>,
     pub created_by: TaskCreator,
+    pub creator_user_id: Option<i64>,
+    pub start_at: Option<String>,
+    pub end_at: Option<String>,
+    pub max_runs: Option<i64>,
+    pub misfire_policy: MisfirePolicy,
+    pub max_retries: Option<i64>,
+    pub urgent: bool,
+    pub calendar: TaskCalendar,
+}
+
+#[derive(Debug, Clone)]
+/// 可清除的字段使用 Option<Option<_>>：None 表示不修改，Some(None) 表示清除
+pub struct UpdateTaskRequest {
+    pub task_id: i64,
+    pub frequency: Option<TaskFrequency>,
+    pub cron_expr: Option<String>,
+    pub content: Option<String>,
+    pub context: Option<Option<String>>,
+    pub end_at: Option<Option<String>>,
+    pub max_runs: Option<Option<i64>>,
+    pub misfire_policy: Option<MisfirePolicy>,
+    pub max_retries: Option<Option<i64>>,
+    pub urgent: Option<bool>,
+    pub calendar: Option<TaskCalendar>,
 }
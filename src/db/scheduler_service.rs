@@ -2,21 +2,40 @@ use anyhow::{Result, anyhow};
 use sqlx::SqlitePool;
 use tracing::debug;
 
-use super::scheduler_model::{CreateTaskRequest, ScheduledTask, TaskCreator, TaskFrequency};
-
-type TaskRow = (
-    i64,
-    String,
-    String,
-    i64,
-    String,
-    String,
-    bool,
-    Option<String>,
-    Option<String>,
-    String,
-    String,
-);
+use super::scheduler_model::{
+    CreateTaskRequest, MisfirePolicy, ScheduledTask, TaskCalendar, TaskCreator, TaskFrequency,
+    UpdateTaskRequest,
+};
+
+const TASK_COLUMNS: &str = r#"
+    id, frequency, cron_expr, target_user_id, content, context, created_by, creator_user_id, enabled,
+    start_at, end_at, max_runs, run_count, misfire_policy, max_retries, urgent, calendar, last_run_at, next_run_at, created_at, updated_at
+"#;
+
+#[derive(sqlx::FromRow)]
+struct TaskRow {
+    id: i64,
+    frequency: String,
+    cron_expr: String,
+    target_user_id: i64,
+    content: String,
+    context: Option<String>,
+    created_by: String,
+    creator_user_id: Option<i64>,
+    enabled: bool,
+    start_at: Option<String>,
+    end_at: Option<String>,
+    max_runs: Option<i64>,
+    run_count: i64,
+    misfire_policy: String,
+    max_retries: Option<i64>,
+    urgent: bool,
+    calendar: String,
+    last_run_at: Option<String>,
+    next_run_at: Option<String>,
+    created_at: String,
+    updated_at: String,
+}
 
 #[derive(Clone)]
 pub struct SchedulerService {
@@ -30,21 +49,33 @@ impl SchedulerService {
 
     pub async fn create_task(&self, req: CreateTaskRequest) -> Result<ScheduledTask> {
         debug!(
-            "创建定时任务: user_id={}, cron={}, created_by={:?}",
-            req.target_user_id, req.cron_expr, req.created_by
+            "创建定时任务: user_id={}, cron={}, created_by={:?}, creator_user_id={:?}",
+            req.target_user_id, req.cron_expr, req.created_by, req.creator_user_id
         );
 
         let result = sqlx::query(
             r#"
-            INSERT INTO scheduled_tasks (frequency, cron_expr, target_user_id, content, created_by)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT INTO scheduled_tasks
+                (frequency, cron_expr, target_user_id, content, context, created_by,
+                 creator_user_id, start_at, end_at, max_runs, misfire_policy, max_retries, urgent,
+                 calendar)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(req.frequency.as_str())
         .bind(&req.cron_expr)
         .bind(req.target_user_id)
         .bind(&req.content)
+        .bind(&req.context)
         .bind(req.created_by.as_str())
+        .bind(req.creator_user_id)
+        .bind(&req.start_at)
+        .bind(&req.end_at)
+        .bind(req.max_runs)
+        .bind(req.misfire_policy.as_str())
+        .bind(req.max_retries)
+        .bind(req.urgent)
+        .bind(req.calendar.as_str())
         .execute(&self.pool)
         .await?;
 
@@ -59,14 +90,13 @@ impl SchedulerService {
     pub async fn get_task(&self, task_id: i64) -> Result<Option<ScheduledTask>> {
         debug!("查询定时任务: id={}", task_id);
 
-        let row = sqlx::query_as::<_, TaskRow>(
+        let row = sqlx::query_as::<_, TaskRow>(&format!(
             r#"
-            SELECT id, frequency, cron_expr, target_user_id, content,
-                   created_by, enabled, last_run_at, next_run_at, created_at, updated_at
+            SELECT {TASK_COLUMNS}
             FROM scheduled_tasks
             WHERE id = ?
-            "#,
-        )
+            "#
+        ))
         .bind(task_id)
         .fetch_optional(&self.pool)
         .await?;
@@ -77,14 +107,92 @@ impl SchedulerService {
     pub async fn get_enabled_tasks(&self) -> Result<Vec<ScheduledTask>> {
         debug!("查询所有启用的定时任务");
 
-        let rows = sqlx::query_as::<_, TaskRow>(
+        let rows = sqlx::query_as::<_, TaskRow>(&format!(
             r#"
-            SELECT id, frequency, cron_expr, target_user_id, content,
-                   created_by, enabled, last_run_at, next_run_at, created_at, updated_at
+            SELECT {TASK_COLUMNS}
             FROM scheduled_tasks
             WHERE enabled = 1
+            "#
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut tasks = Vec::new();
+        for row in rows {
+            if let Some(task) = self.map_row_to_task(Some(row))? {
+                tasks.push(task);
+            }
+        }
+
+        debug!("查询到 {} 个启用的定时任务", tasks.len());
+        Ok(tasks)
+    }
+
+    /// 查询用户收到的以及用户为他人创建的定时任务
+    pub async fn get_tasks_for_user(&self, user_id: i64) -> Result<Vec<ScheduledTask>> {
+        debug!("查询用户的定时任务: user_id={}", user_id);
+
+        let rows = sqlx::query_as::<_, TaskRow>(&format!(
+            r#"
+            SELECT {TASK_COLUMNS}
+            FROM scheduled_tasks
+            WHERE (target_user_id = ? OR creator_user_id = ?) AND created_by = 'user'
+            ORDER BY id
+            "#
+        ))
+        .bind(user_id)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut tasks = Vec::new();
+        for row in rows {
+            if let Some(task) = self.map_row_to_task(Some(row))? {
+                tasks.push(task);
+            }
+        }
+
+        debug!("查询到 {} 个用户定时任务", tasks.len());
+        Ok(tasks)
+    }
+
+    pub async fn count_active_tasks_created_by(&self, user_id: i64) -> Result<i64> {
+        debug!("统计用户创建的启用任务数: user_id={}", user_id);
+
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM scheduled_tasks
+            WHERE creator_user_id = ? AND created_by = 'user' AND enabled = 1
             "#,
         )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    pub async fn get_upcoming_tasks_for_user(
+        &self,
+        user_id: i64,
+        until: &str,
+    ) -> Result<Vec<ScheduledTask>> {
+        debug!(
+            "查询用户即将执行的定时任务: user_id={}, until={}",
+            user_id, until
+        );
+
+        let rows = sqlx::query_as::<_, TaskRow>(&format!(
+            r#"
+            SELECT {TASK_COLUMNS}
+            FROM scheduled_tasks
+            WHERE target_user_id = ? AND created_by = 'user' AND enabled = 1
+                AND next_run_at IS NOT NULL AND next_run_at <= ?
+            ORDER BY next_run_at
+            "#
+        ))
+        .bind(user_id)
+        .bind(until)
         .fetch_all(&self.pool)
         .await?;
 
@@ -95,21 +203,20 @@ impl SchedulerService {
             }
         }
 
-        debug!("查询到 {} 个启用的定时任务", tasks.len());
+        debug!("查询到 {} 个即将执行的定时任务", tasks.len());
         Ok(tasks)
     }
 
     pub async fn get_system_tasks_for_user(&self, user_id: i64) -> Result<Vec<ScheduledTask>> {
         debug!("查询用户的系统定时任务: user_id={}", user_id);
 
-        let rows = sqlx::query_as::<_, TaskRow>(
+        let rows = sqlx::query_as::<_, TaskRow>(&format!(
             r#"
-            SELECT id, frequency, cron_expr, target_user_id, content,
-                   created_by, enabled, last_run_at, next_run_at, created_at, updated_at
+            SELECT {TASK_COLUMNS}
             FROM scheduled_tasks
             WHERE target_user_id = ? AND created_by = 'system'
-            "#,
-        )
+            "#
+        ))
         .bind(user_id)
         .fetch_all(&self.pool)
         .await?;
@@ -140,27 +247,119 @@ impl SchedulerService {
         Ok(deleted)
     }
 
+    pub async fn increment_run_count(&self, task_id: i64) -> Result<()> {
+        debug!("增加任务触发次数: id={}", task_id);
+
+        sqlx::query("UPDATE scheduled_tasks SET run_count = run_count + 1 WHERE id = ?")
+            .bind(task_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn update_last_run(&self, task_id: i64) -> Result<()> {
         debug!("更新任务最后执行时间: id={}", task_id);
 
-        sqlx::query(
+        sqlx::query("UPDATE scheduled_tasks SET last_run_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(task_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_next_run(&self, task_id: i64, next_run_at: Option<&str>) -> Result<()> {
+        debug!(
+            "更新任务下次执行时间: id={}, next_run_at={:?}",
+            task_id, next_run_at
+        );
+
+        sqlx::query("UPDATE scheduled_tasks SET next_run_at = ? WHERE id = ?")
+            .bind(next_run_at)
+            .bind(task_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn disable_task(&self, task_id: i64) -> Result<()> {
+        debug!("禁用定时任务: id={}", task_id);
+
+        sqlx::query("UPDATE scheduled_tasks SET enabled = 0, next_run_at = NULL WHERE id = ?")
+            .bind(task_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn enable_task(&self, task_id: i64) -> Result<()> {
+        debug!("启用定时任务: id={}", task_id);
+
+        sqlx::query("UPDATE scheduled_tasks SET enabled = 1 WHERE id = ?")
+            .bind(task_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_task(&self, req: UpdateTaskRequest) -> Result<ScheduledTask> {
+        debug!(
+            "更新定时任务: id={}, frequency={:?}, cron={:?}",
+            req.task_id, req.frequency, req.cron_expr
+        );
+
+        let rows_affected = sqlx::query(
             r#"
             UPDATE scheduled_tasks
-            SET last_run_at = CURRENT_TIMESTAMP
+            SET frequency = COALESCE(?, frequency),
+                cron_expr = COALESCE(?, cron_expr),
+                content = COALESCE(?, content),
+                context = CASE WHEN ? THEN ? ELSE context END,
+                end_at = CASE WHEN ? THEN ? ELSE end_at END,
+                max_runs = CASE WHEN ? THEN ? ELSE max_runs END,
+                misfire_policy = COALESCE(?, misfire_policy),
+                max_retries = CASE WHEN ? THEN ? ELSE max_retries END,
+                urgent = COALESCE(?, urgent),
+                calendar = COALESCE(?, calendar)
             WHERE id = ?
             "#,
         )
-        .bind(task_id)
+        .bind(req.frequency.map(|f| f.as_str().to_string()))
+        .bind(&req.cron_expr)
+        .bind(&req.content)
+        .bind(req.context.is_some())
+        .bind(req.context.flatten())
+        .bind(req.end_at.is_some())
+        .bind(req.end_at.flatten())
+        .bind(req.max_runs.is_some())
+        .bind(req.max_runs.flatten())
+        .bind(req.misfire_policy.map(|p| p.as_str().to_string()))
+        .bind(req.max_retries.is_some())
+        .bind(req.max_retries.flatten())
+        .bind(req.urgent)
+        .bind(req.calendar.map(|c| c.as_str().to_string()))
+        .bind(req.task_id)
         .execute(&self.pool)
-        .await?;
+        .await?
+        .rows_affected();
 
-        Ok(())
+        if rows_affected == 0 {
+            return Err(anyhow!("任务 ID {} 不存在", req.task_id));
+        }
+
+        self.get_task(req.task_id)
+            .await?
+            .ok_or_else(|| anyhow!("更新任务后无法查询到任务"))
     }
 
-    pub async fn disable_task(&self, task_id: i64) -> Result<()> {
-        debug!("禁用定时任务: id={}", task_id);
+    pub async fn delete_task(&self, task_id: i64) -> Result<()> {
+        debug!("删除定时任务: id={}", task_id);
 
-        sqlx::query("UPDATE scheduled_tasks SET enabled = 0 WHERE id = ?")
+        sqlx::query("DELETE FROM scheduled_tasks WHERE id = ?")
             .bind(task_id)
             .execute(&self.pool)
             .await?;
@@ -170,34 +369,34 @@ impl SchedulerService {
 
     fn map_row_to_task(&self, row: Option<TaskRow>) -> Result<Option<ScheduledTask>> {
         match row {
-            Some((
-                id,
-                frequency_str,
-                cron_expr,
-                target_user_id,
-                content,
-                created_by_str,
-                enabled,
-                last_run_at,
-                next_run_at,
-                created_at,
-                updated_at,
-            )) => {
-                let frequency = TaskFrequency::from_str(&frequency_str)?;
-                let created_by = TaskCreator::from_str(&created_by_str)?;
+            Some(row) => {
+                let frequency = TaskFrequency::from_str(&row.frequency)?;
+                let created_by = TaskCreator::from_str(&row.created_by)?;
+                let misfire_policy = MisfirePolicy::from_str(&row.misfire_policy)?;
+                let calendar = TaskCalendar::from_str(&row.calendar)?;
 
                 Ok(Some(ScheduledTask {
-                    id,
+                    id: row.id,
                     frequency,
-                    cron_expr,
-                    target_user_id,
-                    content,
+                    cron_expr: row.cron_expr,
+                    target_user_id: row.target_user_id,
+                    content: row.content,
+                    context: row.context,
                     created_by,
-                    enabled,
-                    last_run_at,
-                    next_run_at,
-                    created_at,
-                    updated_at,
+                    creator_user_id: row.creator_user_id,
+                    enabled: row.enabled,
+                    start_at: row.start_at,
+                    end_at: row.end_at,
+                    max_runs: row.max_runs,
+                    run_count: row.run_count,
+                    misfire_policy,
+                    max_retries: row.max_retries,
+                    urgent: row.urgent,
+                    calendar,
+                    last_run_at: row.last_run_at,
+                    next_run_at: row.next_run_at,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
                 }))
             }
             None => Ok(None),
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedSearch {
+    pub query_key: String,
+    pub provider: String,
+    pub results: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SaveSearchRequest {
+    pub query_key: String,
+    pub provider: String,
+    pub results: String,
+}
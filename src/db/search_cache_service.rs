@@ -0,0 +1,89 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use tracing::debug;
+
+use super::search_cache_model::{CachedSearch, SaveSearchRequest};
+
+#[derive(Clone)]
+pub struct SearchCacheService {
+    pool: SqlitePool,
+}
+
+impl SearchCacheService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get(&self, query_key: &str, ttl_secs: u64) -> Result<Option<CachedSearch>> {
+        debug!("查询搜索缓存: key={}", query_key);
+
+        let row = sqlx::query_as::<_, (String, String, String, String)>(
+            r#"
+            SELECT query_key, provider, results, created_at
+            FROM search_cache
+            WHERE query_key = ? AND created_at >= datetime('now', ?)
+            "#,
+        )
+        .bind(query_key)
+        .bind(format!("-{} seconds", ttl_secs))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(
+            row.map(|(query_key, provider, results, created_at)| CachedSearch {
+                query_key,
+                provider,
+                results,
+                created_at,
+            }),
+        )
+    }
+
+    pub async fn save(&self, req: SaveSearchRequest) -> Result<()> {
+        debug!(
+            "写入搜索缓存: key={}, provider={}",
+            req.query_key, req.provider
+        );
+
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO search_cache (query_key, provider, results, created_at)
+            VALUES (?, ?, ?, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(&req.query_key)
+        .bind(&req.provider)
+        .bind(&req.results)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn prune(&self, ttl_secs: u64, max_entries: usize) -> Result<u64> {
+        debug!(
+            "清理搜索缓存: ttl={}s, max_entries={}",
+            ttl_secs, max_entries
+        );
+
+        let result = sqlx::query(
+            r#"
+            DELETE FROM search_cache
+            WHERE created_at < datetime('now', ?)
+               OR query_key NOT IN (
+                   SELECT query_key FROM search_cache
+                   ORDER BY created_at DESC
+                   LIMIT ?
+               )
+            "#,
+        )
+        .bind(format!("-{} seconds", ttl_secs))
+        .bind(max_entries as i64)
+        .execute(&self.pool)
+        .await?;
+
+        let deleted = result.rows_affected();
+        debug!("删除了 {} 条过期搜索缓存", deleted);
+        Ok(deleted)
+    }
+}
@@ -0,0 +1,71 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskRunStatus {
+    Success,
+    LlmError,
+    SendError,
+    UserMissing,
+    Deferred,
+    Dropped,
+    QueueError,
+}
+
+impl TaskRunStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TaskRunStatus::Success => "success",
+            TaskRunStatus::LlmError => "llm_error",
+            TaskRunStatus::SendError => "send_error",
+            TaskRunStatus::UserMissing => "user_missing",
+            TaskRunStatus::Deferred => "deferred",
+            TaskRunStatus::Dropped => "dropped",
+            TaskRunStatus::QueueError => "queue_error",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "success" => Ok(TaskRunStatus::Success),
+            "llm_error" => Ok(TaskRunStatus::LlmError),
+            "send_error" => Ok(TaskRunStatus::SendError),
+            "user_missing" => Ok(TaskRunStatus::UserMissing),
+            "deferred" => Ok(TaskRunStatus::Deferred),
+            "dropped" => Ok(TaskRunStatus::Dropped),
+            "queue_error" => Ok(TaskRunStatus::QueueError),
+            _ => Err(anyhow!("无效的任务执行状态: {}", s)),
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, TaskRunStatus::LlmError | TaskRunStatus::SendError)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRun {
+    pub id: i64,
+    pub task_id: Option<i64>,
+    pub target_user_id: i64,
+    pub attempt: i64,
+    pub status: TaskRunStatus,
+    pub error: Option<String>,
+    pub scheduled_at: String,
+    pub started_at: String,
+    pub latency_ms: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateTaskRunRequest {
+    pub task_id: Option<i64>,
+    pub target_user_id: i64,
+    pub attempt: i64,
+    pub status: TaskRunStatus,
+    pub error: Option<String>,
+    pub scheduled_at: String,
+    pub started_at: String,
+    pub latency_ms: i64,
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use tracing::debug;
+
+use super::task_run_model::{CreateTaskRunRequest, TaskRun, TaskRunStatus};
+
+#[derive(sqlx::FromRow)]
+struct TaskRunRow {
+    id: i64,
+    task_id: Option<i64>,
+    target_user_id: i64,
+    attempt: i64,
+    status: String,
+    error: Option<String>,
+    scheduled_at: String,
+    started_at: String,
+    latency_ms: i64,
+    created_at: String,
+}
+
+#[derive(Clone)]
+pub struct TaskRunService {
+    pool: SqlitePool,
+}
+
+impl TaskRunService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create_run(&self, req: CreateTaskRunRequest) -> Result<()> {
+        debug!(
+            "记录任务执行结果: task_id={:?}, user_id={}, attempt={}, status={}",
+            req.task_id,
+            req.target_user_id,
+            req.attempt,
+            req.status.as_str()
+        );
+
+        sqlx::query(
+            r#"
+            INSERT INTO task_runs
+                (task_id, target_user_id, attempt, status, error, scheduled_at, started_at, latency_ms)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(req.task_id)
+        .bind(req.target_user_id)
+        .bind(req.attempt)
+        .bind(req.status.as_str())
+        .bind(&req.error)
+        .bind(&req.scheduled_at)
+        .bind(&req.started_at)
+        .bind(req.latency_ms)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_recent_failures(&self, limit: i64) -> Result<Vec<TaskRun>> {
+        debug!("查询最近失败的任务执行记录: limit={}", limit);
+
+        let rows = sqlx::query_as::<_, TaskRunRow>(
+            r#"
+            SELECT id, task_id, target_user_id, attempt, status, error,
+                   scheduled_at, started_at, latency_ms, created_at
+            FROM task_runs
+            WHERE status != 'success'
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(Self::map_row_to_run).collect()
+    }
+
+    fn map_row_to_run(row: TaskRunRow) -> Result<TaskRun> {
+        Ok(TaskRun {
+            id: row.id,
+            task_id: row.task_id,
+            target_user_id: row.target_user_id,
+            attempt: row.attempt,
+            status: TaskRunStatus::from_str(&row.status)?,
+            error: row.error,
+            scheduled_at: row.scheduled_at,
+            started_at: row.started_at,
+            latency_ms: row.latency_ms,
+            created_at: row.created_at,
+        })
+    }
+}
@@ -34,6 +34,16 @@ pub struct User {
     pub name: String,
     pub relation: UserRelation,
     pub custom_prompt: Option<String>,
+    pub timezone: Option<String>,
+    pub greetings_enabled: bool,
+    pub greeting_categories: Option<String>,
+    pub quiet_start: Option<String>,
+    pub quiet_end: Option<String>,
+    pub dnd_until: Option<String>,
+    pub last_active_at: Option<String>,
+    pub last_message: Option<String>,
+    pub last_checkin_at: Option<String>,
+    pub checkin_count: i64,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -62,3 +72,35 @@ pub struct UpdateUserRequest {
     pub user_id: i64,
     pub relation: UserRelation,
 }
+
+#[derive(Debug, Clone)]
+pub struct UpdateTimezoneRequest {
+    pub id: i64,
+    pub timezone: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct UpdateGreetingPreferenceRequest {
+    pub id: i64,
+    pub greetings_enabled: bool,
+    pub greeting_categories: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UpdateQuietHoursRequest {
+    pub id: i64,
+    pub quiet_start: Option<String>,
+    pub quiet_end: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UpdateDndRequest {
+    pub id: i64,
+    pub dnd_until: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UpdateActivityRequest {
+    pub id: i64,
+    pub last_message: Option<String>,
+}
@@ -3,10 +3,37 @@ use sqlx::SqlitePool;
 use tracing::debug;
 
 use super::user_model::{
-    CreateCustomPromptRequest, CreateMasterRequest, CreateUserRequest, UpdateUserRequest, User,
-    UserRelation,
+    CreateCustomPromptRequest, CreateMasterRequest, CreateUserRequest, UpdateActivityRequest,
+    UpdateDndRequest, UpdateGreetingPreferenceRequest, UpdateQuietHoursRequest,
+    UpdateTimezoneRequest, UpdateUserRequest, User, UserRelation,
 };
 
+const USER_COLUMNS: &str = r#"
+    id, name, relation, custom_prompt, timezone, greetings_enabled, greeting_categories,
+    quiet_start, quiet_end, dnd_until, last_active_at, last_message, last_checkin_at,
+    checkin_count, created_at, updated_at
+"#;
+
+#[derive(sqlx::FromRow)]
+struct UserRow {
+    id: i64,
+    name: String,
+    relation: String,
+    custom_prompt: Option<String>,
+    timezone: Option<String>,
+    greetings_enabled: bool,
+    greeting_categories: Option<String>,
+    quiet_start: Option<String>,
+    quiet_end: Option<String>,
+    dnd_until: Option<String>,
+    last_active_at: Option<String>,
+    last_message: Option<String>,
+    last_checkin_at: Option<String>,
+    checkin_count: i64,
+    created_at: String,
+    updated_at: String,
+}
+
 #[derive(Clone)]
 pub struct UserService {
     pool: SqlitePool,
@@ -17,12 +44,29 @@ impl UserService {
         Self { pool }
     }
 
+    /// 好友以 guest 身份创建，之前通过群聊认识的 stranger 加为好友后升级为 guest
     pub async fn create_user(&self, req: CreateUserRequest) -> Result<User> {
         debug!("创建用户请求: id={}, name={}", req.id, req.name);
 
         if let Some(existing_user) = self.get_user(req.id).await? {
-            debug!("用户已存在: id={}", req.id);
-            return Ok(existing_user);
+            if existing_user.relation != UserRelation::Stranger {
+                debug!("用户已存在: id={}", req.id);
+                return Ok(existing_user);
+            }
+
+            sqlx::query(
+                "UPDATE users SET relation = 'guest' WHERE id = ? AND relation = 'stranger'",
+            )
+            .bind(req.id)
+            .execute(&self.pool)
+            .await?;
+
+            debug!("陌生人用户升级为 guest: id={}", req.id);
+
+            return self
+                .get_user(req.id)
+                .await?
+                .ok_or_else(|| anyhow!("升级用户为 guest 后无法查询到用户"));
         }
 
         sqlx::query(
@@ -43,6 +87,33 @@ impl UserService {
             .ok_or_else(|| anyhow!("创建用户后无法查询到用户"))
     }
 
+    /// 通过群聊或临时会话联系的非好友以 stranger 身份创建，已存在的用户保持原有关系
+    pub async fn create_stranger(&self, req: CreateUserRequest) -> Result<User> {
+        debug!("创建陌生人用户请求: id={}, name={}", req.id, req.name);
+
+        if let Some(existing_user) = self.get_user(req.id).await? {
+            debug!("用户已存在: id={}", req.id);
+            return Ok(existing_user);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO users (id, name, relation)
+            VALUES (?, ?, 'stranger')
+            "#,
+        )
+        .bind(req.id)
+        .bind(&req.name)
+        .execute(&self.pool)
+        .await?;
+
+        debug!("陌生人用户创建成功: id={}", req.id);
+
+        self.get_user(req.id)
+            .await?
+            .ok_or_else(|| anyhow!("创建用户后无法查询到用户"))
+    }
+
     pub async fn create_master(&self, req: CreateMasterRequest) -> Result<User> {
         debug!("创建 master 用户请求: id={}, name={}", req.id, req.name);
 
@@ -88,6 +159,139 @@ impl UserService {
             .ok_or_else(|| anyhow!("更新自定义提示词后无法查询到用户"))
     }
 
+    pub async fn update_timezone(&self, req: UpdateTimezoneRequest) -> Result<User> {
+        debug!("更新用户时区: id={}, timezone={}", req.id, req.timezone);
+
+        let rows_affected = sqlx::query("UPDATE users SET timezone = ? WHERE id = ?")
+            .bind(&req.timezone)
+            .bind(req.id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(anyhow!("用户 ID {} 不存在", req.id));
+        }
+
+        debug!("用户时区更新成功: id={}", req.id);
+
+        self.get_user(req.id)
+            .await?
+            .ok_or_else(|| anyhow!("更新时区后无法查询到用户"))
+    }
+
+    pub async fn update_greeting_preference(
+        &self,
+        req: UpdateGreetingPreferenceRequest,
+    ) -> Result<User> {
+        debug!(
+            "更新用户问候偏好: id={}, enabled={}, categories={:?}",
+            req.id, req.greetings_enabled, req.greeting_categories
+        );
+
+        let rows_affected = sqlx::query(
+            "UPDATE users SET greetings_enabled = ?, greeting_categories = ? WHERE id = ?",
+        )
+        .bind(req.greetings_enabled)
+        .bind(&req.greeting_categories)
+        .bind(req.id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(anyhow!("用户 ID {} 不存在", req.id));
+        }
+
+        debug!("用户问候偏好更新成功: id={}", req.id);
+
+        self.get_user(req.id)
+            .await?
+            .ok_or_else(|| anyhow!("更新问候偏好后无法查询到用户"))
+    }
+
+    pub async fn update_quiet_hours(&self, req: UpdateQuietHoursRequest) -> Result<User> {
+        debug!(
+            "更新用户安静时段: id={}, start={:?}, end={:?}",
+            req.id, req.quiet_start, req.quiet_end
+        );
+
+        let rows_affected =
+            sqlx::query("UPDATE users SET quiet_start = ?, quiet_end = ? WHERE id = ?")
+                .bind(&req.quiet_start)
+                .bind(&req.quiet_end)
+                .bind(req.id)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(anyhow!("用户 ID {} 不存在", req.id));
+        }
+
+        self.get_user(req.id)
+            .await?
+            .ok_or_else(|| anyhow!("更新安静时段后无法查询到用户"))
+    }
+
+    pub async fn update_dnd(&self, req: UpdateDndRequest) -> Result<User> {
+        debug!("更新用户免打扰: id={}, until={:?}", req.id, req.dnd_until);
+
+        let rows_affected = sqlx::query("UPDATE users SET dnd_until = ? WHERE id = ?")
+            .bind(&req.dnd_until)
+            .bind(req.id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(anyhow!("用户 ID {} 不存在", req.id));
+        }
+
+        self.get_user(req.id)
+            .await?
+            .ok_or_else(|| anyhow!("更新免打扰后无法查询到用户"))
+    }
+
+    /// 记录用户的最近活跃时间，并清零未回复的关怀次数
+    pub async fn update_activity(&self, req: UpdateActivityRequest) -> Result<()> {
+        debug!("更新用户活跃时间: id={}", req.id);
+
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET last_active_at = CURRENT_TIMESTAMP,
+                last_message = COALESCE(?, last_message),
+                checkin_count = 0
+            WHERE id = ?
+            "#,
+        )
+        .bind(&req.last_message)
+        .bind(req.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn record_checkin(&self, user_id: i64) -> Result<()> {
+        debug!("记录主动关怀: id={}", user_id);
+
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET last_checkin_at = CURRENT_TIMESTAMP,
+                checkin_count = checkin_count + 1
+            WHERE id = ?
+            "#,
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn update_user(&self, req: UpdateUserRequest) -> Result<User> {
         debug!(
             "更新用户请求: operator_id={}, user_id={}, relation={:?}",
@@ -115,32 +319,25 @@ impl UserService {
     pub async fn get_user(&self, user_id: i64) -> Result<Option<User>> {
         debug!("查询用户: id={}", user_id);
 
-        let row = sqlx::query_as::<_, (i64, String, String, Option<String>, String, String)>(
+        let row = sqlx::query_as::<_, UserRow>(&format!(
             r#"
-            SELECT id, name, relation, custom_prompt, created_at, updated_at
+            SELECT {USER_COLUMNS}
             FROM users
             WHERE id = ?
-            "#,
-        )
+            "#
+        ))
         .bind(user_id)
         .fetch_optional(&self.pool)
         .await?;
 
         match row {
-            Some((id, name, relation_str, custom_prompt, created_at, updated_at)) => {
-                let relation = UserRelation::from_str(&relation_str)?;
+            Some(row) => {
+                let user = Self::map_row_to_user(row)?;
                 debug!(
                     "用户查询成功: id={}, name={}, relation={:?}",
-                    id, name, relation
+                    user.id, user.name, user.relation
                 );
-                Ok(Some(User {
-                    id,
-                    name,
-                    relation,
-                    custom_prompt,
-                    created_at,
-                    updated_at,
-                }))
+                Ok(Some(user))
             }
             None => {
                 debug!("用户不存在: id={}", user_id);
@@ -149,7 +346,7 @@ impl UserService {
         }
     }
 
-    async fn is_master(&self, user_id: i64) -> Result<bool> {
+    pub async fn is_master(&self, user_id: i64) -> Result<bool> {
         debug!("检查是否为 master: id={}", user_id);
 
         let user = self.get_user(user_id).await?;
@@ -181,29 +378,42 @@ impl UserService {
     pub async fn get_all_users(&self) -> Result<Vec<User>> {
         debug!("查询所有用户");
 
-        let rows = sqlx::query_as::<_, (i64, String, String, Option<String>, String, String)>(
+        let rows = sqlx::query_as::<_, UserRow>(&format!(
             r#"
-            SELECT id, name, relation, custom_prompt, created_at, updated_at
+            SELECT {USER_COLUMNS}
             FROM users
-            "#,
-        )
+            "#
+        ))
         .fetch_all(&self.pool)
         .await?;
 
-        let mut users = Vec::new();
-        for (id, name, relation_str, custom_prompt, created_at, updated_at) in rows {
-            let relation = UserRelation::from_str(&relation_str)?;
-            users.push(User {
-                id,
-                name,
-                relation,
-                custom_prompt,
-                created_at,
-                updated_at,
-            });
-        }
+        let users = rows
+            .into_iter()
+            .map(Self::map_row_to_user)
+            .collect::<Result<Vec<_>>>()?;
 
         debug!("查询到 {} 个用户", users.len());
         Ok(users)
     }
+
+    fn map_row_to_user(row: UserRow) -> Result<User> {
+        Ok(User {
+            id: row.id,
+            name: row.name,
+            relation: UserRelation::from_str(&row.relation)?,
+            custom_prompt: row.custom_prompt,
+            timezone: row.timezone,
+            greetings_enabled: row.greetings_enabled,
+            greeting_categories: row.greeting_categories,
+            quiet_start: row.quiet_start,
+            quiet_end: row.quiet_end,
+            dnd_until: row.dnd_until,
+            last_active_at: row.last_active_at,
+            last_message: row.last_message,
+            last_checkin_at: row.last_checkin_at,
+            checkin_count: row.checkin_count,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
 }
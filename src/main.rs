@@ -7,15 +7,21 @@ mod scheduler;
 mod utils;
 
 use anyhow::Result;
-use bot::Bot;
+use bot::{Bot, BotServices};
 use config::Config;
+use db::agent_job_service::AgentJobService;
+use db::group_service::GroupService;
+use db::reminder_message_service::ReminderMessageService;
 use db::scheduler_service::SchedulerService;
+use db::search_cache_service::SearchCacheService;
+use db::task_run_service::TaskRunService;
 use db::user_service::UserService;
 use milky_rust_sdk::prelude::Event;
 use milky_rust_sdk::{Communication, MilkyClient, WebSocketConfig};
 use scheduler::Actuator;
+use scheduler::queue::JobQueue;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use tokio::sync::{Semaphore, mpsc};
 use tracing::{debug, info};
 
 #[tokio::main]
@@ -26,7 +32,12 @@ async fn main() -> Result<()> {
 
     let pool = db::init_db(&config.database.url, config.database.max_connections).await?;
     let user_service = UserService::new(pool.clone());
-    let scheduler_service = SchedulerService::new(pool);
+    let group_service = GroupService::new(pool.clone());
+    let scheduler_service = SchedulerService::new(pool.clone());
+    let task_run_service = TaskRunService::new(pool.clone());
+    let reminder_message_service = ReminderMessageService::new(pool.clone());
+    let job_queue = JobQueue::new(AgentJobService::new(pool.clone()));
+    let search_cache_service = SearchCacheService::new(pool);
     debug!("数据库初始化成功");
 
     let (event_tx, event_rx) = mpsc::channel::<Event>(config.bot.event_channel_capacity);
@@ -41,13 +52,41 @@ async fn main() -> Result<()> {
     )?);
     debug!("MilkyClient 初始化成功");
 
-    let actuator = Actuator::new(user_service.clone(), config.bot.agent_task_channel_capacity);
+    // 聊天消息和定时任务共用同一个并发上限
+    let semaphore = Arc::new(Semaphore::new(config.bot.max_concurrent_tasks));
+
+    let actuator = Actuator::new(
+        user_service.clone(),
+        task_run_service,
+        reminder_message_service,
+        job_queue,
+        Arc::clone(&semaphore),
+    );
     let (agent, scheduler_manager) = actuator
-        .start(scheduler_service, &config.llm, Arc::clone(&client))
+        .start(
+            scheduler_service,
+            &config.scheduler,
+            &config.llm,
+            &config.search,
+            search_cache_service,
+            Arc::clone(&client),
+        )
         .await?;
     debug!("Actuator 初始化成功");
 
-    let bot = Bot::new(&config.bot, user_service, client, event_rx, agent).await?;
+    let bot = Bot::new(
+        semaphore,
+        BotServices {
+            user_service,
+            group_service,
+            agent,
+            scheduler_manager: Arc::clone(&scheduler_manager),
+        },
+        client,
+        event_rx,
+        &config.bot,
+    )
+    .await?;
     let bot_handle = bot.run().await?;
     debug!("Bot 初始化成功");
 
@@ -1,5 +1,11 @@
 pub mod actuator;
+pub mod cron;
+pub mod greetings;
+pub mod holidays;
+pub mod ics;
 pub mod manager;
+pub mod queue;
+pub mod reminder_reply;
 
 pub use actuator::Actuator;
 pub use manager::SchedulerManager;
@@ -1,37 +1,71 @@
-use crate::agent::{Agent, AgentTask};
-use crate::config::LLMConfig;
+use crate::agent::{Agent, AgentTask, DealError};
+use crate::config::{LLMConfig, SchedulerConfig, SearchConfig};
+use crate::db::agent_job_model::AgentJobStatus;
+use crate::db::reminder_message_service::ReminderMessageService;
+use crate::db::scheduler_model::{ScheduledTask, TaskCreator};
 use crate::db::scheduler_service::SchedulerService;
+use crate::db::search_cache_service::SearchCacheService;
+use crate::db::task_run_model::{CreateTaskRunRequest, TaskRunStatus};
+use crate::db::task_run_service::TaskRunService;
+use crate::db::user_model::User;
 use crate::db::user_service::UserService;
 use crate::scheduler::SchedulerManager;
+use crate::scheduler::queue::{JobQueue, QueuedJob};
+use crate::utils::format_db_datetime;
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use milky_rust_sdk::MilkyClient;
 use std::sync::Arc;
-use tokio::sync::mpsc;
-use tracing::{debug, error};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tracing::{debug, error, info, warn};
 
 pub struct Actuator {
     user_service: UserService,
-    channel_capacity: usize,
+    task_run_service: TaskRunService,
+    reminder_message_service: ReminderMessageService,
+    queue: JobQueue,
+    semaphore: Arc<Semaphore>,
 }
 
 impl Actuator {
-    pub fn new(user_service: UserService, channel_capacity: usize) -> Self {
+    pub fn new(
+        user_service: UserService,
+        task_run_service: TaskRunService,
+        reminder_message_service: ReminderMessageService,
+        queue: JobQueue,
+        semaphore: Arc<Semaphore>,
+    ) -> Self {
         Self {
             user_service,
-            channel_capacity,
+            task_run_service,
+            reminder_message_service,
+            queue,
+            semaphore,
         }
     }
 
     pub async fn start(
         self,
         scheduler_service: SchedulerService,
+        scheduler_config: &SchedulerConfig,
         llm_config: &LLMConfig,
+        search_config: &SearchConfig,
+        search_cache_service: SearchCacheService,
         client: Arc<MilkyClient>,
     ) -> Result<(Arc<Agent>, Arc<SchedulerManager>)> {
-        let (task_tx, task_rx) = mpsc::channel(self.channel_capacity);
+        self.queue.recover().await?;
 
         let scheduler_manager = Arc::new(
-            SchedulerManager::new(scheduler_service, self.user_service.clone(), task_tx).await?,
+            SchedulerManager::new(
+                scheduler_service.clone(),
+                self.user_service.clone(),
+                self.task_run_service.clone(),
+                self.reminder_message_service,
+                self.queue.clone(),
+                scheduler_config,
+            )
+            .await?,
         );
 
         scheduler_manager.start().await?;
@@ -39,48 +73,281 @@ impl Actuator {
 
         let agent = Arc::new(Agent::new(
             llm_config,
+            search_config,
+            search_cache_service,
             client,
             Arc::clone(&scheduler_manager),
         )?);
         debug!("Agent 初始化成功");
 
-        let user_service = self.user_service;
-        let agent_clone = Arc::clone(&agent);
-
-        tokio::spawn(async move {
-            Self::run(agent_clone, user_service, task_rx).await;
+        let runner = Arc::new(TaskRunner {
+            agent: Arc::clone(&agent),
+            scheduler_manager: Arc::clone(&scheduler_manager),
+            user_service: self.user_service,
+            scheduler_service,
+            task_run_service: self.task_run_service,
+            queue: self.queue,
+            max_retries: scheduler_config.max_retries,
+            retry_delay: Duration::from_secs(scheduler_config.retry_delay_secs),
         });
-        debug!("Actuator 任务循环已启动");
+
+        let workers = scheduler_config.workers.max(1);
+        for worker in 0..workers {
+            let runner = Arc::clone(&runner);
+            let semaphore = Arc::clone(&self.semaphore);
+            tokio::spawn(async move {
+                Self::run(worker, runner, semaphore).await;
+            });
+        }
+        debug!("Actuator 任务循环已启动: workers={}", workers);
 
         Ok((agent, scheduler_manager))
     }
 
-    async fn run(
-        agent: Arc<Agent>,
-        user_service: UserService,
-        mut task_rx: mpsc::Receiver<AgentTask>,
-    ) {
-        while let Some(task) = task_rx.recv().await {
+    async fn run(worker: usize, runner: Arc<TaskRunner>, semaphore: Arc<Semaphore>) {
+        loop {
+            let job = runner.queue.next().await;
+
+            // 与聊天消息共用并发上限，取到任务后再申请槽位，避免空闲时占用
+            let _permit = match Arc::clone(&semaphore).acquire_owned().await {
+                Ok(permit) => permit,
+                Err(e) => {
+                    error!("获取任务槽位失败: {}", e);
+                    return;
+                }
+            };
+
+            let wait_ms = (Utc::now() - job.available_at).num_milliseconds().max(0);
+            let queue_depth = runner.queue.depth().await;
+            info!(
+                target: "metrics",
+                worker,
+                queue_depth,
+                wait_ms,
+                "agent_queue"
+            );
+
             debug!(
-                "收到定时任务: target_user_id={}, content={}",
-                task.target_user_id, task.content
+                "收到定时任务: worker={}, job_id={}, task_id={:?}, target_user_id={}, attempt={}, content={}",
+                worker,
+                job.job_id,
+                job.task.task_id,
+                job.task.target_user_id,
+                job.task.attempt,
+                job.task.content
             );
 
-            match user_service.get_user(task.target_user_id).await {
-                Ok(Some(user)) => {
-                    if let Err(e) = agent.deal(&user, &task.content).await {
-                        error!("执行定时任务失败: {}", e);
+            runner.execute(job).await;
+        }
+    }
+}
+
+enum QuietDecision {
+    Deliver,
+    Defer(DateTime<Utc>),
+    Drop,
+}
+
+struct TaskRunner {
+    agent: Arc<Agent>,
+    scheduler_manager: Arc<SchedulerManager>,
+    user_service: UserService,
+    scheduler_service: SchedulerService,
+    task_run_service: TaskRunService,
+    queue: JobQueue,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+impl TaskRunner {
+    async fn execute(&self, job: QueuedJob) {
+        let QueuedJob { job_id, task, .. } = job;
+        let started_at = Utc::now();
+        let mut deferred_until = None;
+
+        let scheduled = match task.task_id {
+            Some(task_id) => self
+                .scheduler_service
+                .get_task(task_id)
+                .await
+                .unwrap_or_else(|e| {
+                    error!("查询任务失败: id={}, error={}", task_id, e);
+                    None
+                }),
+            None => None,
+        };
+
+        let (status, error) = match self.user_service.get_user(task.target_user_id).await {
+            Ok(Some(user)) => match self.quiet_decision(&user, scheduled.as_ref()) {
+                QuietDecision::Deliver => match self
+                    .agent
+                    .deal(
+                        &user,
+                        &task.content,
+                        scheduled.as_ref().and_then(|s| s.context.as_deref()),
+                    )
+                    .await
+                {
+                    Ok(message_seqs) => {
+                        self.record_reminder(scheduled.as_ref(), message_seqs).await;
+                        (TaskRunStatus::Success, None)
                     }
+                    Err(e @ DealError::Llm(_)) => (TaskRunStatus::LlmError, Some(e.to_string())),
+                    Err(e @ DealError::Send(_)) => (TaskRunStatus::SendError, Some(e.to_string())),
+                },
+                QuietDecision::Defer(until) => {
+                    deferred_until = Some(until);
+                    (
+                        TaskRunStatus::Deferred,
+                        Some(format!("用户处于安静时段，推迟到 {}", until)),
+                    )
                 }
-                Ok(None) => {
-                    error!("用户不存在: user_id={}", task.target_user_id);
-                }
+                QuietDecision::Drop => (
+                    TaskRunStatus::Dropped,
+                    Some("用户处于安静时段，已丢弃系统问候".to_string()),
+                ),
+            },
+            Ok(None) => (
+                TaskRunStatus::UserMissing,
+                Some(format!("用户不存在: user_id={}", task.target_user_id)),
+            ),
+            Err(e) => (
+                TaskRunStatus::UserMissing,
+                Some(format!("查询用户失败: {}", e)),
+            ),
+        };
+
+        let latency_ms = (started_at - task.scheduled_at).num_milliseconds().max(0);
+
+        match &error {
+            Some(error) if matches!(status, TaskRunStatus::Deferred | TaskRunStatus::Dropped) => {
+                info!(
+                    "定时任务未投递: task_id={:?}, status={}, reason={}",
+                    task.task_id,
+                    status.as_str(),
+                    error
+                )
+            }
+            Some(error) => error!(
+                "执行定时任务失败: task_id={:?}, status={}, error={}",
+                task.task_id,
+                status.as_str(),
+                error
+            ),
+            None => debug!(
+                "定时任务执行成功: task_id={:?}, latency_ms={}",
+                task.task_id, latency_ms
+            ),
+        }
+
+        let req = CreateTaskRunRequest {
+            task_id: task.task_id,
+            target_user_id: task.target_user_id,
+            attempt: task.attempt as i64,
+            status,
+            error: error.clone(),
+            scheduled_at: format_db_datetime(&task.scheduled_at),
+            started_at: format_db_datetime(&started_at),
+            latency_ms,
+        };
+        if let Err(e) = self.task_run_service.create_run(req).await {
+            error!("记录任务执行结果失败: {}", e);
+        }
+
+        if let Some(task_id) = task.task_id
+            && status == TaskRunStatus::Success
+            && let Err(e) = self.scheduler_service.update_last_run(task_id).await
+        {
+            error!("更新任务执行时间失败: {}", e);
+        }
+
+        if let Some(until) = deferred_until {
+            if let Err(e) = self.queue.reschedule(job_id, task.attempt, until).await {
+                error!("推迟队列任务失败: id={}, error={}", job_id, e);
+            }
+        } else if status.is_retryable() {
+            self.schedule_retry(job_id, &task, error.as_deref()).await;
+        } else {
+            let job_status = match status {
+                TaskRunStatus::UserMissing => AgentJobStatus::Failed,
+                _ => AgentJobStatus::Done,
+            };
+            self.queue
+                .finish(job_id, job_status, error.as_deref())
+                .await;
+        }
+    }
+
+    /// 根据用户的安静时段和免打扰状态决定任务如何处理
+    fn quiet_decision(&self, user: &User, scheduled: Option<&ScheduledTask>) -> QuietDecision {
+        let Some(until) = self.scheduler_manager.quiet_until(user) else {
+            return QuietDecision::Deliver;
+        };
+
+        match scheduled {
+            Some(scheduled) if scheduled.urgent => QuietDecision::Deliver,
+            Some(scheduled) if scheduled.created_by == TaskCreator::System => QuietDecision::Drop,
+            _ => QuietDecision::Defer(until),
+        }
+    }
+
+    /// 用户创建的提醒记录下发出的消息，以便识别用户的快捷回复
+    async fn record_reminder(&self, scheduled: Option<&ScheduledTask>, message_seqs: Vec<i64>) {
+        let Some(scheduled) = scheduled.filter(|s| s.created_by == TaskCreator::User) else {
+            return;
+        };
+
+        if let Err(e) = self
+            .scheduler_manager
+            .record_reminder_messages(scheduled.target_user_id, scheduled.id, message_seqs)
+            .await
+        {
+            error!("记录提醒消息失败: task_id={}, error={}", scheduled.id, e);
+        }
+    }
+
+    async fn schedule_retry(&self, job_id: i64, task: &AgentTask, error: Option<&str>) {
+        let max_retries = match task.task_id {
+            Some(task_id) => match self.scheduler_service.get_task(task_id).await {
+                Ok(Some(scheduled)) => scheduled
+                    .max_retries
+                    .map(|n| u32::try_from(n.max(0)).unwrap_or(u32::MAX))
+                    .unwrap_or(self.max_retries),
+                Ok(None) => 0,
                 Err(e) => {
-                    error!("查询用户失败: user_id={}, error={}", task.target_user_id, e);
+                    error!("查询任务重试策略失败: id={}, error={}", task_id, e);
+                    self.max_retries
                 }
-            }
+            },
+            None => self.max_retries,
+        };
+
+        if task.attempt >= max_retries {
+            warn!(
+                "任务重试次数已用尽: task_id={:?}, attempt={}",
+                task.task_id, task.attempt
+            );
+            self.queue
+                .finish(job_id, AgentJobStatus::Failed, error)
+                .await;
+            return;
         }
 
-        debug!("Actuator 任务循环已退出");
+        let delay = self.retry_delay * 2u32.saturating_pow(task.attempt);
+        info!(
+            "任务将在 {} 秒后重试: task_id={:?}, attempt={}",
+            delay.as_secs(),
+            task.task_id,
+            task.attempt + 1
+        );
+
+        let available_at = Utc::now() + delay;
+        if let Err(e) = self
+            .queue
+            .reschedule(job_id, task.attempt + 1, available_at)
+            .await
+        {
+            error!("重新投递任务失败: id={}, error={}", job_id, e);
+        }
     }
 }
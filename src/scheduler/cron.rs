@@ -0,0 +1,111 @@
+use crate::db::scheduler_model::TaskFrequency;
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Timelike, Utc, Weekday};
+use croner::Cron;
+use croner::parser::{CronParser, Seconds};
+
+pub fn parse_cron(cron_expr: &str) -> Result<Cron> {
+    CronParser::builder()
+        .seconds(Seconds::Required)
+        .dom_and_dow(true)
+        .build()
+        .parse(cron_expr)
+        .map_err(|e| anyhow!("无效的 Cron 表达式 '{}': {}", cron_expr, e))
+}
+
+/// 检查 Cron 表达式的日期和星期字段是否符合任务频率，cron 和 once 不做限制
+pub fn validate_frequency(frequency: TaskFrequency, cron_expr: &str) -> Result<()> {
+    parse_cron(cron_expr)?;
+
+    let fields: Vec<&str> = cron_expr.split_whitespace().collect();
+    let [_, _, _, dom, month, dow, ..] = fields.as_slice() else {
+        bail!(
+            "Cron 表达式 '{}' 需要包含 秒 分 时 日 月 星期 六个字段",
+            cron_expr
+        );
+    };
+    let is_any = |field: &str| field == "*" || field == "?";
+    let weekdays = cron_weekdays(dow)?;
+
+    let valid = match frequency {
+        TaskFrequency::Once | TaskFrequency::Cron => true,
+        TaskFrequency::Daily => is_any(dom) && is_any(month) && is_any(dow),
+        TaskFrequency::Weekly => {
+            is_any(dom) && is_any(month) && !weekdays.is_empty() && weekdays.len() < 7
+        }
+        TaskFrequency::Weekdays => {
+            is_any(dom)
+                && is_any(month)
+                && weekdays
+                    == [
+                        Weekday::Mon,
+                        Weekday::Tue,
+                        Weekday::Wed,
+                        Weekday::Thu,
+                        Weekday::Fri,
+                    ]
+        }
+        TaskFrequency::Monthly => !is_any(dom) && is_any(month) && is_any(dow),
+    };
+
+    if !valid {
+        bail!(
+            "Cron 表达式 '{}' 与任务频率 {} 不符：daily 的日、月、星期都应为 *，weekly 需要指定星期且日为 *，weekdays 的星期应为 MON-FRI，monthly 需要指定日且星期为 *，其他规则请使用 cron",
+            cron_expr,
+            frequency.as_str()
+        );
+    }
+    Ok(())
+}
+
+/// 计算星期字段匹配的星期几，按周一到周日排列
+pub(crate) fn cron_weekdays(dow: &str) -> Result<Vec<Weekday>> {
+    let probe = parse_cron(&format!("0 0 0 * * {}", dow))?;
+    // 2024-01-01 是周一
+    let monday = NaiveDate::from_ymd_opt(2024, 1, 1).ok_or_else(|| anyhow!("无效的日期"))?;
+
+    let mut weekdays = Vec::new();
+    for date in monday.iter_days().take(7) {
+        let Some(midnight) = date.and_hms_opt(0, 0, 0) else {
+            continue;
+        };
+        if probe
+            .is_time_matching(&Utc.from_utc_datetime(&midnight))
+            .map_err(|e| anyhow!("无法解析星期字段 '{}': {}", dow, e))?
+        {
+            weekdays.push(date.weekday());
+        }
+    }
+    Ok(weekdays)
+}
+
+/// 生成只在指定时刻触发一次的 Cron 表达式
+pub fn once_cron<Tz: TimeZone>(at: &DateTime<Tz>) -> String {
+    format!(
+        "{} {} {} {} {} *",
+        at.second(),
+        at.minute(),
+        at.hour(),
+        at.day(),
+        at.month()
+    )
+}
+
+/// 将 Cron 表达式的星期字段改为每天，是否执行改由节假日日历决定
+pub fn every_day_cron(cron_expr: &str) -> Result<String> {
+    parse_cron(cron_expr)?;
+
+    let mut fields: Vec<&str> = cron_expr.split_whitespace().collect();
+    if let Some(dow) = fields.last_mut() {
+        *dow = "*";
+    }
+    Ok(fields.join(" "))
+}
+
+pub fn next_fire_time<Tz: TimeZone>(cron_expr: &str, after: &DateTime<Tz>) -> Result<DateTime<Tz>> {
+    let after = after.with_nanosecond(0).unwrap_or_else(|| after.clone());
+
+    parse_cron(cron_expr)?
+        .find_next_occurrence(&after, false)
+        .map_err(|e| anyhow!("无法计算 Cron 表达式 '{}' 的下次触发时间: {}", cron_expr, e))
+}
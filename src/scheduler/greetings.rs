@@ -0,0 +1,256 @@
+use crate::db::user_model::{User, UserRelation};
+use anyhow::{Context, Result, bail};
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GreetingDays {
+    #[default]
+    All,
+    Weekday,
+    Weekend,
+}
+
+impl GreetingDays {
+    fn matches(&self, weekday: Weekday) -> bool {
+        let is_weekend = matches!(weekday, Weekday::Sat | Weekday::Sun);
+        match self {
+            GreetingDays::All => true,
+            GreetingDays::Weekday => !is_weekend,
+            GreetingDays::Weekend => is_weekend,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Greeting {
+    pub category: String,
+    pub content: String,
+    pub hour_range: (u32, u32),
+    #[serde(default)]
+    pub days: GreetingDays,
+    #[serde(default = "default_relations")]
+    pub relations: Vec<UserRelation>,
+    #[serde(default = "default_probability")]
+    pub probability: f64,
+    #[serde(default = "default_per_day")]
+    pub min_per_day: u32,
+    #[serde(default = "default_per_day")]
+    pub max_per_day: u32,
+}
+
+fn default_relations() -> Vec<UserRelation> {
+    vec![UserRelation::Master, UserRelation::Guest]
+}
+
+fn default_probability() -> f64 {
+    1.0
+}
+
+fn default_per_day() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GreetingCatalogue {
+    #[serde(default)]
+    pub greetings: Vec<Greeting>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlannedGreeting {
+    pub category: String,
+    pub content: String,
+    pub time: NaiveTime,
+}
+
+impl GreetingCatalogue {
+    pub fn builtin() -> Self {
+        let greeting = |category: &str, content: &str, hour_range: (u32, u32)| Greeting {
+            category: category.to_string(),
+            content: content.to_string(),
+            hour_range,
+            days: GreetingDays::All,
+            relations: default_relations(),
+            probability: default_probability(),
+            min_per_day: default_per_day(),
+            max_per_day: default_per_day(),
+        };
+
+        Self {
+            greetings: vec![
+                greeting(
+                    "morning",
+                    "现在是早上，请给用户发送一条温馨的早安问候，可以包含今日天气、励志语句等。",
+                    (7, 9),
+                ),
+                greeting(
+                    "noon",
+                    "现在是中午，请给用户发送一条午间问候，提醒用户注意休息和用餐。",
+                    (11, 13),
+                ),
+                greeting(
+                    "night",
+                    "现在是晚上，请给用户发送一条晚安问候，祝用户有个好梦。",
+                    (21, 23),
+                ),
+                greeting(
+                    "water",
+                    "请用可爱、关心的语气提醒用户喝水，保持身体健康。",
+                    (9, 18),
+                ),
+                greeting(
+                    "stretch",
+                    "请用关心的语气提醒用户起来活动一下，避免久坐对身体的伤害。",
+                    (10, 17),
+                ),
+            ],
+        }
+    }
+
+    pub fn categories(&self) -> Vec<String> {
+        self.greetings
+            .iter()
+            .map(|g| g.category.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// 为用户生成指定日期的问候计划，categories 为空表示接收全部类别
+    pub fn plan_for(
+        &self,
+        user: &User,
+        categories: &[String],
+        date: NaiveDate,
+    ) -> Vec<PlannedGreeting> {
+        let mut rng = rand::rng();
+        let mut planned = Vec::new();
+
+        for greeting in &self.greetings {
+            if !greeting.relations.contains(&user.relation)
+                || !greeting.days.matches(date.weekday())
+                || (!categories.is_empty() && !categories.contains(&greeting.category))
+            {
+                continue;
+            }
+
+            if !rng.random_bool(greeting.probability.clamp(0.0, 1.0)) {
+                continue;
+            }
+
+            let count = rng.random_range(greeting.min_per_day..=greeting.max_per_day);
+            for _ in 0..count {
+                let hour = rng.random_range(greeting.hour_range.0..=greeting.hour_range.1);
+                let minute = rng.random_range(0..60);
+                if let Some(time) = NaiveTime::from_hms_opt(hour, minute, 0) {
+                    planned.push(PlannedGreeting {
+                        category: greeting.category.clone(),
+                        content: greeting.content.clone(),
+                        time,
+                    });
+                }
+            }
+        }
+
+        planned.sort_by_key(|g| g.time);
+        planned
+    }
+
+    fn validate(&self) -> Result<()> {
+        for greeting in &self.greetings {
+            let (start, end) = greeting.hour_range;
+            if start > end || end > 23 {
+                bail!(
+                    "问候 {} 的 hour_range 无效: ({}, {})",
+                    greeting.category,
+                    start,
+                    end
+                );
+            }
+            if greeting.min_per_day > greeting.max_per_day {
+                bail!(
+                    "问候 {} 的 min_per_day 不能大于 max_per_day",
+                    greeting.category
+                );
+            }
+            if !(0.0..=1.0).contains(&greeting.probability) {
+                bail!(
+                    "问候 {} 的 probability 必须在 0 到 1 之间",
+                    greeting.category
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct CatalogueState {
+    modified: Option<SystemTime>,
+    catalogue: Arc<GreetingCatalogue>,
+}
+
+/// 问候目录文件，文件修改后在下次读取时自动重新加载
+pub struct GreetingStore {
+    path: PathBuf,
+    state: Mutex<CatalogueState>,
+}
+
+impl GreetingStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let store = Self {
+            path: path.into(),
+            state: Mutex::new(CatalogueState {
+                modified: None,
+                catalogue: Arc::new(GreetingCatalogue::builtin()),
+            }),
+        };
+
+        if !store.path.exists() {
+            warn!("问候配置文件不存在，使用内置问候: {}", store.path.display());
+        }
+        store.current();
+        store
+    }
+
+    pub fn current(&self) -> Arc<GreetingCatalogue> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+
+        if modified.is_some() && modified != state.modified {
+            match self.load() {
+                Ok(catalogue) => {
+                    info!(
+                        "已加载问候配置: {}，共 {} 条",
+                        self.path.display(),
+                        catalogue.greetings.len()
+                    );
+                    state.catalogue = Arc::new(catalogue);
+                }
+                Err(e) => error!("加载问候配置失败，继续使用旧配置: {:#}", e),
+            }
+            state.modified = modified;
+        }
+
+        Arc::clone(&state.catalogue)
+    }
+
+    fn load(&self) -> Result<GreetingCatalogue> {
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("无法读取问候配置文件: {}", self.path.display()))?;
+        let catalogue: GreetingCatalogue =
+            toml::from_str(&content).context("问候配置文件格式错误")?;
+        catalogue.validate()?;
+        Ok(catalogue)
+    }
+}
@@ -0,0 +1,153 @@
+use anyhow::{Context, Result, anyhow};
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tracing::{error, info, warn};
+
+/// 节假日配置文件格式，日期写作 "2026-10-01" 或区间 "2026-10-01..2026-10-07"
+#[derive(Debug, Default, Deserialize)]
+struct HolidayFile {
+    #[serde(default)]
+    holidays: Vec<String>,
+    #[serde(default)]
+    workdays: Vec<String>,
+}
+
+/// 法定节假日与调休上班日
+#[derive(Debug, Clone, Default)]
+pub struct HolidayCalendar {
+    holidays: BTreeSet<NaiveDate>,
+    workdays: BTreeSet<NaiveDate>,
+}
+
+impl HolidayCalendar {
+    /// 调休上班日视为工作日，法定节假日视为休息日，其余按周一至周五判断
+    pub fn is_workday(&self, date: NaiveDate) -> bool {
+        if self.workdays.contains(&date) {
+            return true;
+        }
+        if self.holidays.contains(&date) {
+            return false;
+        }
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let file: HolidayFile = toml::from_str(content).context("节假日配置文件格式错误")?;
+
+        Ok(Self {
+            holidays: parse_dates(&file.holidays)?,
+            workdays: parse_dates(&file.workdays)?,
+        })
+    }
+
+    fn last_year(&self) -> Option<i32> {
+        self.holidays
+            .iter()
+            .chain(self.workdays.iter())
+            .map(|d| d.year())
+            .max()
+    }
+}
+
+fn parse_dates(entries: &[String]) -> Result<BTreeSet<NaiveDate>> {
+    let parse = |value: &str| {
+        NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+            .map_err(|_| anyhow!("无法解析日期 '{}'，请使用 YYYY-MM-DD 格式", value))
+    };
+
+    let mut dates = BTreeSet::new();
+    for entry in entries {
+        match entry.split_once("..") {
+            Some((start, end)) => {
+                let (start, end) = (parse(start)?, parse(end)?);
+                if start > end {
+                    return Err(anyhow!("日期区间 '{}' 的开始日期晚于结束日期", entry));
+                }
+                dates.extend(start.iter_days().take_while(|d| *d <= end));
+            }
+            None => {
+                dates.insert(parse(entry)?);
+            }
+        }
+    }
+
+    Ok(dates)
+}
+
+struct CalendarState {
+    modified: Option<SystemTime>,
+    calendar: Arc<HolidayCalendar>,
+}
+
+/// 节假日文件，每年更新文件后在下次读取时自动重新加载
+pub struct HolidayStore {
+    path: PathBuf,
+    state: Mutex<CalendarState>,
+}
+
+impl HolidayStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let store = Self {
+            path: path.into(),
+            state: Mutex::new(CalendarState {
+                modified: None,
+                calendar: Arc::new(HolidayCalendar::default()),
+            }),
+        };
+
+        if !store.path.exists() {
+            warn!(
+                "节假日配置文件不存在，工作日仅按周一至周五判断: {}",
+                store.path.display()
+            );
+        }
+        store.current();
+        store
+    }
+
+    pub fn current(&self) -> Arc<HolidayCalendar> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+
+        if modified.is_some() && modified != state.modified {
+            match self.load() {
+                Ok(calendar) => {
+                    info!(
+                        "已加载节假日配置: {}，节假日 {} 天，调休上班 {} 天",
+                        self.path.display(),
+                        calendar.holidays.len(),
+                        calendar.workdays.len()
+                    );
+                    state.calendar = Arc::new(calendar);
+                }
+                Err(e) => error!("加载节假日配置失败，继续使用旧配置: {:#}", e),
+            }
+            state.modified = modified;
+        }
+
+        Arc::clone(&state.calendar)
+    }
+
+    fn load(&self) -> Result<HolidayCalendar> {
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("无法读取节假日配置文件: {}", self.path.display()))?;
+        let calendar = HolidayCalendar::parse(&content)?;
+
+        let this_year = chrono::Local::now().year();
+        if calendar.last_year().is_none_or(|year| year < this_year) {
+            warn!(
+                "节假日配置文件未包含 {} 年的数据，请及时更新: {}",
+                this_year,
+                self.path.display()
+            );
+        }
+
+        Ok(calendar)
+    }
+}
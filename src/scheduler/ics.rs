@@ -0,0 +1,744 @@
+use crate::db::scheduler_model::{ScheduledTask, TaskCalendar, TaskFrequency};
+use crate::scheduler::cron::{cron_weekdays, once_cron};
+use crate::utils::parse_db_datetime;
+use anyhow::{Result, anyhow, bail};
+use chrono::{
+    DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeDelta, TimeZone, Timelike,
+    Utc, Weekday,
+};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+use std::collections::{BTreeSet, HashMap};
+
+/// 全天事件导入后的提醒时刻
+const ALL_DAY_REMINDER_TIME: (u32, u32) = (9, 0);
+
+/// iCalendar 内容行的最大字节数，超出部分需要折行
+const MAX_LINE_OCTETS: usize = 75;
+
+/// VTIMEZONE 列出的偏移切换年数，之后沿用最后一次切换后的偏移
+const TIMEZONE_YEARS: i64 = 10;
+
+const ICS_WEEKDAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun),
+];
+
+pub struct CalendarExport {
+    pub content: String,
+    pub exported: usize,
+    pub skipped: usize,
+}
+
+/// 从日历文件中解析出的提醒
+pub struct ImportedTask {
+    pub frequency: TaskFrequency,
+    pub cron_expr: String,
+    pub first_run: DateTime<Tz>,
+    /// 重复日程的开始时间，只在开始时间还没到时设置
+    pub start_at: Option<DateTime<Utc>>,
+    pub end_at: Option<DateTime<Utc>>,
+    pub max_runs: Option<i64>,
+}
+
+pub struct ImportedEvent {
+    pub summary: String,
+    pub description: Option<String>,
+    pub task: Result<ImportedTask>,
+}
+
+/// 将启用的任务导出为 iCalendar，无法用 RRULE 表示的 cron 任务会被跳过
+pub fn render_calendar(tasks: &[ScheduledTask], timezone: Tz) -> CalendarExport {
+    let now = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//millkrs//reminders//ZH".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-TIMEZONE:{}", timezone.name()),
+    ];
+    lines.extend(render_timezone(timezone, Utc::now()));
+    let (mut exported, mut skipped) = (0, 0);
+
+    for task in tasks {
+        let Some(event) = render_event(task, timezone, &now) else {
+            skipped += 1;
+            continue;
+        };
+        lines.extend(event);
+        exported += 1;
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let content = lines
+        .iter()
+        .map(|line| fold_line(line))
+        .collect::<Vec<_>>()
+        .join("\r\n");
+
+    CalendarExport {
+        content: content + "\r\n",
+        exported,
+        skipped,
+    }
+}
+
+fn render_event(task: &ScheduledTask, timezone: Tz, now: &str) -> Option<Vec<String>> {
+    let start = parse_db_datetime(task.next_run_at.as_deref()?)?.with_timezone(&timezone);
+
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:millkrs-task-{}", task.id),
+        format!("DTSTAMP:{}", now),
+        format!(
+            "DTSTART;TZID={}:{}",
+            timezone.name(),
+            start.format("%Y%m%dT%H%M%S")
+        ),
+        format!("SUMMARY:{}", escape_text(task.content.lines().next()?)),
+        format!("DESCRIPTION:{}", escape_text(&task.content)),
+    ];
+
+    if task.frequency != TaskFrequency::Once {
+        let mut rrule = cron_to_rrule(&task.cron_expr, task.calendar)?;
+        if let Some(max_runs) = task.max_runs {
+            rrule.push_str(&format!(";COUNT={}", (max_runs - task.run_count).max(1)));
+        } else if let Some(end_at) = task.end_at.as_deref().and_then(parse_db_datetime) {
+            rrule.push_str(&format!(";UNTIL={}", end_at.format("%Y%m%dT%H%M%SZ")));
+        }
+        lines.push(format!("RRULE:{}", rrule));
+    }
+
+    lines.push("END:VEVENT".to_string());
+    Some(lines)
+}
+
+/// 生成事件 DTSTART 引用的 VTIMEZONE，列出从 from 开始若干年内的偏移切换
+fn render_timezone(timezone: Tz, from: DateTime<Utc>) -> Vec<String> {
+    let offset_at = |t: DateTime<Utc>| t.with_timezone(&timezone).offset().fix().local_minus_utc();
+    let until = from + TimeDelta::days(365 * TIMEZONE_YEARS);
+
+    // 逐日比较偏移，发生变化时二分查找切换的时刻
+    let mut transitions = Vec::new();
+    let mut day = from;
+    while day < until {
+        let next = day + TimeDelta::days(1);
+        if offset_at(day) != offset_at(next) {
+            let (mut before, mut after) = (day, next);
+            while after - before > TimeDelta::seconds(1) {
+                let mid = before + (after - before) / 2;
+                if offset_at(mid) == offset_at(before) {
+                    before = mid;
+                } else {
+                    after = mid;
+                }
+            }
+            transitions.push((after, offset_at(before)));
+        }
+        day = next;
+    }
+
+    let mut lines = vec![
+        "BEGIN:VTIMEZONE".to_string(),
+        format!("TZID:{}", timezone.name()),
+    ];
+    // 第一个时段从 1970 年开始，覆盖导出时刻之前的时间
+    let initial = from.with_timezone(&timezone);
+    lines.extend(render_observance(
+        &initial,
+        offset_at(from),
+        "19700101T000000".to_string(),
+    ));
+    for (at, offset_from) in transitions {
+        // 时段的开始时间是切换前的当地时间
+        let onset = at.naive_utc() + TimeDelta::seconds(i64::from(offset_from));
+        lines.extend(render_observance(
+            &at.with_timezone(&timezone),
+            offset_from,
+            onset.format("%Y%m%dT%H%M%S").to_string(),
+        ));
+    }
+    lines.push("END:VTIMEZONE".to_string());
+    lines
+}
+
+fn render_observance(at: &DateTime<Tz>, offset_from: i32, onset: String) -> Vec<String> {
+    let offset = at.offset();
+    let kind = if offset.dst_offset().is_zero() {
+        "STANDARD"
+    } else {
+        "DAYLIGHT"
+    };
+
+    let mut lines = vec![
+        format!("BEGIN:{}", kind),
+        format!("DTSTART:{}", onset),
+        format!("TZOFFSETFROM:{}", format_utc_offset(offset_from)),
+        format!(
+            "TZOFFSETTO:{}",
+            format_utc_offset(offset.fix().local_minus_utc())
+        ),
+    ];
+    if let Some(name) = offset.abbreviation() {
+        lines.push(format!("TZNAME:{}", name));
+    }
+    lines.push(format!("END:{}", kind));
+    lines
+}
+
+fn format_utc_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.unsigned_abs();
+    format!("{}{:02}{:02}", sign, seconds / 3600, seconds % 3600 / 60)
+}
+
+/// 将 cron 表达式转换为 RRULE，只支持固定时刻按天、星期、月或年重复的表达式
+fn cron_to_rrule(cron_expr: &str, calendar: TaskCalendar) -> Option<String> {
+    let fields: Vec<&str> = cron_expr.split_whitespace().collect();
+    let [second, minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+        return None;
+    };
+
+    for field in [second, minute, hour] {
+        field.parse::<u32>().ok()?;
+    }
+
+    // 工作日日历的任务无法用 RRULE 表达节假日，按周一至周五导出
+    if calendar == TaskCalendar::CnWorkday {
+        return Some("FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR".to_string());
+    }
+
+    let any = |field: &str| field == "*" || field == "?";
+    match (any(day_of_month), any(month), any(day_of_week)) {
+        (true, true, true) => Some("FREQ=DAILY".to_string()),
+        (true, true, false) => {
+            // 第几个星期几（#）和最后一个星期几（L）与月份有关，无法用 BYDAY 表示
+            if day_of_week.contains('#') || day_of_week.to_ascii_uppercase().contains('L') {
+                return None;
+            }
+            let days = cron_weekdays(day_of_week).ok()?;
+            if days.is_empty() {
+                return None;
+            }
+            let byday: Vec<&str> = ICS_WEEKDAYS
+                .iter()
+                .filter(|(_, weekday)| days.contains(weekday))
+                .map(|(name, _)| *name)
+                .collect();
+            Some(format!("FREQ=WEEKLY;BYDAY={}", byday.join(",")))
+        }
+        (false, true, true) => Some(format!(
+            "FREQ=MONTHLY;BYMONTHDAY={}",
+            cron_numbers(day_of_month, 1, 31)?
+        )),
+        (false, false, true) => Some(format!(
+            "FREQ=YEARLY;BYMONTH={};BYMONTHDAY={}",
+            cron_numbers(month, 1, 12)?,
+            cron_numbers(day_of_month, 1, 31)?
+        )),
+        _ => None,
+    }
+}
+
+fn cron_numbers(field: &str, min: u32, max: u32) -> Option<String> {
+    field
+        .split(',')
+        .map(|value| {
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|n| (min..=max).contains(n))
+                .map(|n| n.to_string())
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|values| values.join(","))
+}
+
+/// 解析 iCalendar 文件中的事件，无法转换的事件会附带原因
+pub fn parse_calendar(content: &str, timezone: Tz) -> Result<Vec<ImportedEvent>> {
+    let lines = unfold_lines(content);
+    if !lines
+        .first()
+        .is_some_and(|line| line.eq_ignore_ascii_case("BEGIN:VCALENDAR"))
+    {
+        bail!("不是有效的 iCalendar 文件");
+    }
+
+    let mut events = Vec::new();
+    let mut current: Option<Vec<ContentLine>> = None;
+    // VALARM 等嵌套组件的属性不属于事件本身
+    let mut nested = 0usize;
+
+    for line in &lines {
+        let Some(line) = ContentLine::parse(line) else {
+            continue;
+        };
+        let Some(properties) = current.as_mut() else {
+            if line.name == "BEGIN" && line.value.eq_ignore_ascii_case("VEVENT") {
+                current = Some(Vec::new());
+                nested = 0;
+            }
+            continue;
+        };
+
+        match line.name.as_str() {
+            "END" if nested == 0 && line.value.eq_ignore_ascii_case("VEVENT") => {
+                events.push(parse_event(properties, timezone));
+                current = None;
+            }
+            "BEGIN" => nested += 1,
+            "END" => nested = nested.saturating_sub(1),
+            _ if nested == 0 => properties.push(line),
+            _ => {}
+        }
+    }
+
+    Ok(events)
+}
+
+fn parse_event(properties: &[ContentLine], timezone: Tz) -> ImportedEvent {
+    let find = |name: &str| properties.iter().find(|p| p.name == name);
+
+    let summary = find("SUMMARY")
+        .map(|p| unescape_text(&p.value))
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| "（无标题）".to_string());
+    let description = find("DESCRIPTION")
+        .map(|p| unescape_text(&p.value))
+        .filter(|s| !s.trim().is_empty() && *s != summary);
+
+    let task = match find("DTSTART") {
+        Some(dtstart) => parse_schedule(dtstart, find("RRULE"), timezone),
+        None => Err(anyhow!("缺少开始时间")),
+    };
+
+    ImportedEvent {
+        summary,
+        description,
+        task,
+    }
+}
+
+fn parse_schedule(
+    dtstart: &ContentLine,
+    rrule: Option<&ContentLine>,
+    timezone: Tz,
+) -> Result<ImportedTask> {
+    let start = parse_ics_datetime(dtstart, timezone)?;
+    let time = format!("{} {} {}", start.second(), start.minute(), start.hour());
+
+    let Some(rrule) = rrule else {
+        if start <= Utc::now().with_timezone(&timezone) {
+            bail!("提醒时间已经过去");
+        }
+        return Ok(ImportedTask {
+            frequency: TaskFrequency::Once,
+            cron_expr: once_cron(&start),
+            first_run: start,
+            start_at: None,
+            end_at: None,
+            max_runs: None,
+        });
+    };
+
+    let parts: HashMap<String, String> = rrule
+        .value
+        .split(';')
+        .filter_map(|part| part.split_once('='))
+        .map(|(key, value)| (key.to_ascii_uppercase(), value.to_ascii_uppercase()))
+        .collect();
+
+    if let Some(key) = parts.keys().find(|key| {
+        !matches!(
+            key.as_str(),
+            "FREQ" | "INTERVAL" | "BYDAY" | "BYMONTHDAY" | "BYMONTH" | "UNTIL" | "COUNT" | "WKST"
+        )
+    }) {
+        bail!("不支持的重复规则 {}", key);
+    }
+    if parts
+        .get("INTERVAL")
+        .is_some_and(|interval| interval != "1")
+    {
+        bail!("不支持间隔重复（INTERVAL）");
+    }
+
+    let (frequency, cron_expr) = match parts.get("FREQ").map(String::as_str) {
+        Some("DAILY") => (TaskFrequency::Daily, format!("{} * * *", time)),
+        Some("WEEKLY") => {
+            let days = match parts.get("BYDAY") {
+                Some(byday) => byday
+                    .split(',')
+                    .map(|day| {
+                        ICS_WEEKDAYS
+                            .iter()
+                            .find(|(name, _)| *name == day)
+                            .map(|(_, weekday)| *weekday)
+                            .ok_or_else(|| anyhow!("不支持的星期 {}", day))
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+                None => vec![start.weekday()],
+            };
+            let days: BTreeSet<u32> = days.iter().map(|d| d.num_days_from_monday()).collect();
+
+            if days.len() == 7 {
+                (TaskFrequency::Daily, format!("{} * * *", time))
+            } else if days == (0..5).collect() {
+                (TaskFrequency::Weekdays, format!("{} * * MON-FRI", time))
+            } else {
+                let names: Vec<String> = days
+                    .iter()
+                    .filter_map(|d| Weekday::try_from(*d as u8).ok())
+                    .map(|d| d.to_string().to_ascii_uppercase())
+                    .collect();
+                (
+                    TaskFrequency::Weekly,
+                    format!("{} * * {}", time, names.join(",")),
+                )
+            }
+        }
+        Some("MONTHLY") => {
+            if parts.contains_key("BYDAY") {
+                bail!("不支持按第几个星期几重复");
+            }
+            let day = match parts.get("BYMONTHDAY") {
+                Some(day) => {
+                    cron_numbers(day, 1, 31).ok_or_else(|| anyhow!("不支持的日期 {}", day))?
+                }
+                None => start.day().to_string(),
+            };
+            (TaskFrequency::Monthly, format!("{} {} * *", time, day))
+        }
+        Some("YEARLY") => {
+            if parts.contains_key("BYDAY") {
+                bail!("不支持按第几个星期几重复");
+            }
+            let month = match parts.get("BYMONTH") {
+                Some(month) => {
+                    cron_numbers(month, 1, 12).ok_or_else(|| anyhow!("不支持的月份 {}", month))?
+                }
+                None => start.month().to_string(),
+            };
+            let day = match parts.get("BYMONTHDAY") {
+                Some(day) => {
+                    cron_numbers(day, 1, 31).ok_or_else(|| anyhow!("不支持的日期 {}", day))?
+                }
+                None => start.day().to_string(),
+            };
+            (TaskFrequency::Cron, format!("{} {} {} *", time, day, month))
+        }
+        Some(freq) => bail!("不支持的重复频率 {}", freq),
+        None => bail!("重复规则缺少 FREQ"),
+    };
+
+    let end_at = parts
+        .get("UNTIL")
+        .map(|until| parse_until(until, timezone))
+        .transpose()?;
+    let max_runs = parts
+        .get("COUNT")
+        .map(|count| {
+            count
+                .parse::<i64>()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| anyhow!("无效的重复次数 {}", count))
+        })
+        .transpose()?;
+
+    Ok(ImportedTask {
+        frequency,
+        cron_expr,
+        first_run: start,
+        start_at: (start > Utc::now()).then(|| start.to_utc()),
+        end_at,
+        max_runs,
+    })
+}
+
+/// 解析 DTSTART，支持 UTC、带 TZID 的本地时间、浮动时间和全天日期
+fn parse_ics_datetime(line: &ContentLine, timezone: Tz) -> Result<DateTime<Tz>> {
+    let value = line.value.trim();
+    let invalid = || anyhow!("无法解析开始时间 {}", value);
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+        return Ok(Utc.from_utc_datetime(&naive).with_timezone(&timezone));
+    }
+
+    let naive = match NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        Ok(naive) => naive,
+        Err(_) => {
+            let date = NaiveDate::parse_from_str(value, "%Y%m%d").map_err(|_| invalid())?;
+            let (hour, minute) = ALL_DAY_REMINDER_TIME;
+            return date
+                .and_time(NaiveTime::from_hms_opt(hour, minute, 0).ok_or_else(invalid)?)
+                .and_local_timezone(timezone)
+                .earliest()
+                .ok_or_else(invalid);
+        }
+    };
+
+    let source_tz = line
+        .param("TZID")
+        .and_then(|tzid| tzid.parse::<Tz>().ok())
+        .unwrap_or(timezone);
+
+    naive
+        .and_local_timezone(source_tz)
+        .earliest()
+        .map(|t| t.with_timezone(&timezone))
+        .ok_or_else(invalid)
+}
+
+fn parse_until(value: &str, timezone: Tz) -> Result<DateTime<Utc>> {
+    let invalid = || anyhow!("无法解析重复截止时间 {}", value);
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+        return Ok(Utc.from_utc_datetime(&naive));
+    }
+
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").or_else(|_| {
+        NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(23, 59, 59))
+            .ok_or_else(invalid)
+    })?;
+
+    naive
+        .and_local_timezone(timezone)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+        .ok_or_else(invalid)
+}
+
+struct ContentLine {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl ContentLine {
+    fn parse(line: &str) -> Option<Self> {
+        // 参数值可以用引号包裹并包含冒号，需要跳过引号内的冒号
+        let mut in_quotes = false;
+        let colon = line.char_indices().find_map(|(i, c)| match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                None
+            }
+            ':' if !in_quotes => Some(i),
+            _ => None,
+        })?;
+
+        let (head, value) = (&line[..colon], &line[colon + 1..]);
+        let mut parts = head.split(';');
+        let name = parts.next()?.trim().to_ascii_uppercase();
+        let params = parts
+            .filter_map(|param| param.split_once('='))
+            .map(|(key, value)| {
+                (
+                    key.to_ascii_uppercase(),
+                    value.trim_matches('"').to_string(),
+                )
+            })
+            .collect();
+
+        Some(Self {
+            name,
+            params,
+            value: value.to_string(),
+        })
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn unfold_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ if line.is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    lines
+}
+
+/// 按 RFC 5545 将超长的内容行折行，不拆分多字节字符
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut width = 0;
+
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+
+    folded
+}
+
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => {}
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::scheduler_model::{MisfirePolicy, TaskCreator};
+    use crate::utils::format_db_datetime;
+
+    fn test_task(
+        id: i64,
+        frequency: TaskFrequency,
+        cron_expr: &str,
+        content: &str,
+        next_run_at: DateTime<Tz>,
+    ) -> ScheduledTask {
+        let now = format_db_datetime(&Utc::now());
+        ScheduledTask {
+            id,
+            frequency,
+            cron_expr: cron_expr.to_string(),
+            target_user_id: 10001,
+            content: content.to_string(),
+            context: None,
+            created_by: TaskCreator::User,
+            creator_user_id: Some(10001),
+            enabled: true,
+            start_at: None,
+            end_at: None,
+            max_runs: None,
+            run_count: 0,
+            misfire_policy: MisfirePolicy::RunImmediately,
+            max_retries: None,
+            urgent: false,
+            calendar: TaskCalendar::Any,
+            last_run_at: None,
+            next_run_at: Some(format_db_datetime(&next_run_at.to_utc())),
+            created_at: now.clone(),
+            updated_at: now,
+        }
+    }
+
+    /// 导出的日历重新导入后得到相同的提醒，夏令时时区也能按当地时间还原
+    #[test]
+    fn exported_calendar_round_trips() -> Result<()> {
+        let timezone: Tz = "America/New_York".parse().map_err(|e| anyhow!("{e}"))?;
+        let date = (Utc::now() + TimeDelta::days(7))
+            .with_timezone(&timezone)
+            .date_naive();
+        let monday = date - TimeDelta::days(i64::from(date.weekday().num_days_from_monday()));
+        let at = |hour: u32, minute: u32| -> Result<DateTime<Tz>> {
+            monday
+                .and_hms_opt(hour, minute, 0)
+                .and_then(|t| t.and_local_timezone(timezone).earliest())
+                .ok_or_else(|| anyhow!("无效的时间"))
+        };
+
+        let once_at = at(20, 15)?;
+        let long_content = "记得给阳台上的花浇水，顺便检查一下土壤是不是太干了，".repeat(3);
+        let mut daily = test_task(3, TaskFrequency::Daily, "0 0 8 * * *", "喝水", at(8, 0)?);
+        daily.max_runs = Some(5);
+        daily.run_count = 2;
+        let tasks = vec![
+            test_task(
+                1,
+                TaskFrequency::Weekly,
+                "0 30 7 * * MON,WED",
+                &long_content,
+                at(7, 30)?,
+            ),
+            test_task(
+                2,
+                TaskFrequency::Once,
+                &once_cron(&once_at),
+                "开会",
+                once_at,
+            ),
+            daily,
+        ];
+
+        let export = render_calendar(&tasks, timezone);
+        assert_eq!(export.exported, 3);
+        assert_eq!(export.skipped, 0);
+        assert!(export.content.contains("BEGIN:VTIMEZONE"));
+
+        let events = parse_calendar(&export.content, timezone)?;
+        assert_eq!(events.len(), 3);
+
+        assert_eq!(events[0].summary, long_content);
+        let weekly = events[0].task.as_ref().map_err(|e| anyhow!("{e}"))?;
+        assert_eq!(weekly.frequency, TaskFrequency::Weekly);
+        assert_eq!(weekly.cron_expr, "0 30 7 * * MON,WED");
+        assert_eq!(weekly.first_run, at(7, 30)?);
+
+        assert_eq!(events[1].summary, "开会");
+        let once = events[1].task.as_ref().map_err(|e| anyhow!("{e}"))?;
+        assert_eq!(once.frequency, TaskFrequency::Once);
+        assert_eq!(once.cron_expr, once_cron(&once_at));
+
+        let daily = events[2].task.as_ref().map_err(|e| anyhow!("{e}"))?;
+        assert_eq!(daily.frequency, TaskFrequency::Daily);
+        assert_eq!(daily.cron_expr, "0 0 8 * * *");
+        assert_eq!(daily.max_runs, Some(3));
+
+        Ok(())
+    }
+
+    /// 超长的中文内容行按字节折行，不会把多字节字符拆开
+    #[test]
+    fn long_lines_fold_on_character_boundaries() {
+        let line = format!("SUMMARY:{}", "提醒🔔".repeat(30));
+        let folded = fold_line(&line);
+
+        let physical: Vec<&str> = folded.split("\r\n").collect();
+        assert!(physical.len() > 1);
+        assert!(physical.iter().all(|l| l.len() <= MAX_LINE_OCTETS));
+        assert!(physical[1..].iter().all(|l| l.starts_with(' ')));
+
+        assert_eq!(unfold_lines(&folded), vec![line]);
+    }
+}
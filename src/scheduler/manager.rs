@@ -1,69 +1,134 @@
 use crate::agent::AgentTask;
-use crate::db::scheduler_model::{CreateTaskRequest, ScheduledTask, TaskCreator, TaskFrequency};
+use crate::config::{SchedulerConfig, TaskQuotaConfig};
+use crate::db::reminder_message_model::{CreateReminderMessagesRequest, ReminderAck};
+use crate::db::reminder_message_service::ReminderMessageService;
+use crate::db::scheduler_model::{
+    CreateTaskRequest, MisfirePolicy, ScheduledTask, TaskCalendar, TaskCreator, TaskFrequency,
+    UpdateTaskRequest,
+};
 use crate::db::scheduler_service::SchedulerService;
-use crate::db::user_model::UserRelation;
+use crate::db::task_run_model::{CreateTaskRunRequest, TaskRun, TaskRunStatus};
+use crate::db::task_run_service::TaskRunService;
+use crate::db::user_model::{
+    UpdateGreetingPreferenceRequest, UpdateTimezoneRequest, User, UserRelation,
+};
 use crate::db::user_service::UserService;
-use anyhow::Result;
-use chrono::Local;
-use rand::Rng;
-use tokio::sync::{Mutex, mpsc};
+use crate::scheduler::cron::{every_day_cron, next_fire_time, once_cron, validate_frequency};
+use crate::scheduler::greetings::{GreetingCatalogue, GreetingStore};
+use crate::scheduler::holidays::{HolidayCalendar, HolidayStore};
+use crate::scheduler::ics::{CalendarExport, parse_calendar, render_calendar};
+use crate::scheduler::queue::JobQueue;
+use crate::scheduler::reminder_reply::{ReminderReply, parse_reminder_reply};
+use crate::utils::{format_db_datetime, parse_db_datetime, parse_timezone};
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Datelike, NaiveTime, TimeDelta, Timelike, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
 use tokio_cron_scheduler::{Job, JobScheduler};
 use tracing::{debug, error, info};
-
-struct RandomTask {
-    content: &'static str,
-    hour_range: (u32, u32),
-}
-
-const RANDOM_TASKS: &[RandomTask] = &[
-    RandomTask {
-        content: "现在是早上，请给用户发送一条温馨的早安问候，可以包含今日天气、励志语句等。",
-        hour_range: (7, 9),
-    },
-    RandomTask {
-        content: "现在是中午，请给用户发送一条午间问候，提醒用户注意休息和用餐。",
-        hour_range: (11, 13),
-    },
-    RandomTask {
-        content: "现在是晚上，请给用户发送一条晚安问候，祝用户有个好梦。",
-        hour_range: (21, 23),
-    },
-    RandomTask {
-        content: "请用可爱、关心的语气提醒用户喝水，保持身体健康。",
-        hour_range: (9, 18),
-    },
-    RandomTask {
-        content: "请用关心的语气提醒用户起来活动一下，避免久坐对身体的伤害。",
-        hour_range: (10, 17),
-    },
-];
+use uuid::Uuid;
 
 pub struct SchedulerManager {
     scheduler: Mutex<JobScheduler>,
+    jobs: Arc<Mutex<HashMap<i64, Uuid>>>,
     service: SchedulerService,
     user_service: UserService,
-    task_tx: mpsc::Sender<AgentTask>,
+    task_run_service: TaskRunService,
+    reminder_message_service: ReminderMessageService,
+    queue: JobQueue,
+    default_timezone: Tz,
+    misfire_grace: TimeDelta,
+    reminder_reply_window: TimeDelta,
+    greetings: Arc<GreetingStore>,
+    holidays: Arc<HolidayStore>,
+    checkin: CheckinPolicy,
+    task_quota: TaskQuotaConfig,
+    pending_imports: Mutex<HashMap<i64, PendingImport>>,
+}
+
+/// 等待用户确认的日历导入
+struct PendingImport {
+    requests: Vec<CreateTaskRequest>,
+    expires_at: DateTime<Utc>,
+}
+
+/// 主动关怀的触发条件和频率上限
+struct CheckinPolicy {
+    inactive: Option<TimeDelta>,
+    cooldown: TimeDelta,
+    max_unanswered: i64,
 }
 
+/// 定时任务触发时投递到队列所需的服务
+#[derive(Clone)]
+struct TaskDispatcher {
+    service: SchedulerService,
+    task_run_service: TaskRunService,
+    queue: JobQueue,
+    holidays: Arc<HolidayStore>,
+}
+
+/// 只在用户当地的白天发送主动关怀
+const CHECKIN_HOURS: (u32, u32) = (10, 20);
+
+/// 任务保存的对话背景的最大字符数
+const MAX_TASK_CONTEXT_CHARS: usize = 500;
+
+/// 日历导入预览后等待用户确认的时间
+const IMPORT_CONFIRM_MINUTES: i64 = 30;
+
+/// 单个日历文件最多导入的事件数
+const MAX_IMPORT_EVENTS: usize = 50;
+
+/// 计算工作日任务的下次执行时间时最多跳过的触发次数
+const MAX_NON_WORKDAY_SKIPS: usize = 366;
+
 impl SchedulerManager {
     pub async fn new(
         service: SchedulerService,
         user_service: UserService,
-        task_tx: mpsc::Sender<AgentTask>,
+        task_run_service: TaskRunService,
+        reminder_message_service: ReminderMessageService,
+        queue: JobQueue,
+        config: &SchedulerConfig,
     ) -> Result<Self> {
         let scheduler = JobScheduler::new().await?;
+        let default_timezone = parse_timezone(&config.default_timezone)?;
+        let misfire_grace = TimeDelta::seconds(config.misfire_grace_secs as i64);
+        let greetings = Arc::new(GreetingStore::new(&config.greetings_path));
+        let holidays = Arc::new(HolidayStore::new(&config.holidays_path));
+        let checkin = CheckinPolicy {
+            inactive: (config.checkin_inactive_days > 0)
+                .then(|| TimeDelta::days(config.checkin_inactive_days as i64)),
+            cooldown: TimeDelta::days(config.checkin_cooldown_days as i64),
+            max_unanswered: config.checkin_max_unanswered as i64,
+        };
 
         Ok(Self {
             scheduler: Mutex::new(scheduler),
+            jobs: Arc::new(Mutex::new(HashMap::new())),
             service,
             user_service,
-            task_tx,
+            task_run_service,
+            reminder_message_service,
+            queue,
+            default_timezone,
+            misfire_grace,
+            reminder_reply_window: TimeDelta::seconds(config.reminder_reply_window_secs as i64),
+            greetings,
+            holidays,
+            checkin,
+            task_quota: config.task_quota.clone(),
+            pending_imports: Mutex::new(HashMap::new()),
         })
     }
 
-    pub async fn start(&self) -> Result<()> {
+    pub async fn start(self: &Arc<Self>) -> Result<()> {
         self.initialize_random_tasks().await?;
         self.schedule_daily_random_task_update().await?;
+        self.schedule_inactivity_checkin().await?;
         self.load_tasks().await?;
         self.scheduler.lock().await.start().await?;
         info!("定时任务调度器已启动");
@@ -83,16 +148,20 @@ impl SchedulerManager {
         let tasks = self.service.get_enabled_tasks().await?;
 
         for task in tasks {
-            if let Err(e) = self
-                .schedule_task(
-                    task.id,
-                    &task.cron_expr,
-                    task.frequency,
-                    task.target_user_id,
-                    task.content.clone(),
-                )
-                .await
-            {
+            let timezone = self.timezone_for(task.target_user_id).await;
+            if end_condition_met(&task, timezone) {
+                info!("任务已满足结束条件，自动禁用: id={}", task.id);
+                if let Err(e) = self.service.disable_task(task.id).await {
+                    error!("禁用已结束任务失败: id={}, error={}", task.id, e);
+                }
+                continue;
+            }
+
+            if !self.catch_up_missed_task(&task, timezone).await {
+                continue;
+            }
+
+            if let Err(e) = self.schedule_task(&task).await {
                 error!("加载任务失败: id={}, error={}", task.id, e);
             }
         }
@@ -100,72 +169,110 @@ impl SchedulerManager {
         Ok(())
     }
 
+    /// 处理停机期间错过的触发，返回任务是否仍需加入调度器
+    async fn catch_up_missed_task(&self, task: &ScheduledTask, timezone: Tz) -> bool {
+        let Some(missed_at) = missed_fire_time(task, timezone) else {
+            return true;
+        };
+
+        let now = Utc::now();
+        let within_grace = now - missed_at <= self.misfire_grace;
+        let should_run = task.misfire_policy == MisfirePolicy::RunImmediately && within_grace;
+
+        if should_run {
+            info!(
+                "任务在停机期间错过执行，立即补发: id={}, missed_at={}",
+                task.id, missed_at
+            );
+
+            let dispatcher = self.dispatcher();
+            let task = task.clone();
+            tokio::spawn(async move {
+                let agent_task = AgentTask {
+                    task_id: Some(task.id),
+                    target_user_id: task.target_user_id,
+                    content: task.content,
+                    scheduled_at: missed_at,
+                    attempt: 0,
+                };
+                dispatcher
+                    .dispatch(agent_task, task.calendar, task.frequency, timezone)
+                    .await;
+            });
+
+            return task.frequency != TaskFrequency::Once;
+        }
+
+        let expire =
+            task.misfire_policy == MisfirePolicy::Expire || task.frequency == TaskFrequency::Once;
+
+        if expire {
+            info!(
+                "任务在停机期间错过执行，已过期: id={}, missed_at={}, policy={}",
+                task.id,
+                missed_at,
+                task.misfire_policy.as_str()
+            );
+            if let Err(e) = self.service.disable_task(task.id).await {
+                error!("禁用过期任务失败: id={}, error={}", task.id, e);
+            }
+            return false;
+        }
+
+        info!(
+            "任务在停机期间错过执行，跳过本次: id={}, missed_at={}, policy={}",
+            task.id,
+            missed_at,
+            task.misfire_policy.as_str()
+        );
+        true
+    }
+
     async fn initialize_random_tasks(&self) -> Result<()> {
         info!("初始化随机定时任务");
 
         let users = self.user_service.get_all_users().await?;
+        let now = Utc::now();
 
         for user in users {
-            if user.relation == UserRelation::Stranger {
+            if user.relation == UserRelation::Stranger || !user.greetings_enabled {
                 continue;
             }
 
+            let timezone = self.timezone_of(&user);
+            let today = now.with_timezone(&timezone).date_naive();
             let existing_tasks = self.service.get_system_tasks_for_user(user.id).await?;
 
-            let should_recreate = if existing_tasks.is_empty() {
-                true
-            } else {
-                let now = Local::now();
-                existing_tasks.iter().any(|task| {
-                    if let Ok(created) =
-                        chrono::NaiveDateTime::parse_from_str(&task.created_at, "%Y-%m-%d %H:%M:%S")
-                    {
-                        created.date() < now.date_naive()
-                    } else {
-                        true
-                    }
-                })
-            };
+            let should_recreate = existing_tasks.is_empty()
+                || existing_tasks.iter().any(|task| {
+                    parse_db_datetime(&task.created_at)
+                        .map(|created| created.with_timezone(&timezone).date_naive() < today)
+                        .unwrap_or(true)
+                });
 
             if should_recreate {
                 info!("为用户 {} 重新创建随机定时任务", user.id);
-                self.service.delete_system_tasks_for_user(user.id).await?;
-                self.create_random_tasks_for_user(user.id).await?;
+                self.reset_random_tasks_for_user(&user, now).await?;
             }
         }
 
         Ok(())
     }
 
-    async fn schedule_daily_random_task_update(&self) -> Result<()> {
-        let service = self.service.clone();
-        let user_service = self.user_service.clone();
+    /// 每小时检查一次，在用户所在时区的凌晨1点重新生成当天的问候任务
+    async fn schedule_daily_random_task_update(self: &Arc<Self>) -> Result<()> {
+        let manager = Arc::downgrade(self);
 
-        let job = Job::new_async("0 0 1 * * *", move |_uuid, _lock| {
-            let service = service.clone();
-            let user_service = user_service.clone();
+        let job = Job::new_async("0 0 * * * *", move |_uuid, _lock| {
+            let manager = manager.clone();
 
             Box::pin(async move {
-                info!("执行每日随机任务重新生成");
-
-                match user_service.get_all_users().await {
-                    Ok(users) => {
-                        for user in users {
-                            if user.relation == UserRelation::Stranger {
-                                continue;
-                            }
-
-                            if let Err(e) = service.delete_system_tasks_for_user(user.id).await {
-                                error!("删除用户系统任务失败: user_id={}, error={}", user.id, e);
-                                continue;
-                            }
-
-                            if let Err(e) = create_random_tasks(&service, user.id).await {
-                                error!("创建随机任务失败: user_id={}, error={}", user.id, e);
-                            }
-                        }
-                    }
-                    Err(e) => error!("获取用户列表失败: {}", e),
+                let Some(manager) = manager.upgrade() else {
+                    return;
+                };
+
+                if let Err(e) = manager.regenerate_random_tasks(Utc::now()).await {
+                    error!("每日随机任务重新生成失败: {}", e);
                 }
             })
         })?;
@@ -176,122 +283,1495 @@ impl SchedulerManager {
         Ok(())
     }
 
-    async fn create_random_tasks_for_user(&self, user_id: i64) -> Result<()> {
-        for random_task in RANDOM_TASKS {
-            let cron_expr = generate_random_cron(random_task.hour_range);
+    /// 每小时检查一次长时间未说话的用户，发送主动关怀
+    async fn schedule_inactivity_checkin(self: &Arc<Self>) -> Result<()> {
+        if self.checkin.inactive.is_none() {
+            info!("主动关怀已关闭");
+            return Ok(());
+        }
+
+        let manager = Arc::downgrade(self);
 
-            let req = CreateTaskRequest {
-                target_user_id: user_id,
-                frequency: TaskFrequency::Once,
-                cron_expr: cron_expr.clone(),
-                content: random_task.content.to_string(),
-                created_by: TaskCreator::System,
+        let job = Job::new_async("0 30 * * * *", move |_uuid, _lock| {
+            let manager = manager.clone();
+
+            Box::pin(async move {
+                let Some(manager) = manager.upgrade() else {
+                    return;
+                };
+
+                if let Err(e) = manager.send_inactivity_checkins().await {
+                    error!("主动关怀检查失败: {}", e);
+                }
+            })
+        })?;
+
+        self.scheduler.lock().await.add(job).await?;
+        info!("已添加主动关怀检查调度");
+
+        Ok(())
+    }
+
+    async fn send_inactivity_checkins(&self) -> Result<()> {
+        let users = self.user_service.get_all_users().await?;
+        let now = Utc::now();
+
+        for user in users {
+            if !self.checkin_due(&user, now) {
+                continue;
+            }
+
+            let task = AgentTask {
+                task_id: None,
+                target_user_id: user.id,
+                content: checkin_content(&user, now),
+                scheduled_at: now,
+                attempt: 0,
             };
+            let local_date = now.with_timezone(&self.timezone_of(&user)).date_naive();
+            let key = format!("checkin:{}:{}", user.id, local_date);
+
+            match self.queue.push(key, task).await {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    error!("发送主动关怀任务失败: user_id={}, error={}", user.id, e);
+                    continue;
+                }
+            }
+
+            info!(
+                "发送主动关怀: user_id={}, last_active_at={:?}, checkin_count={}",
+                user.id, user.last_active_at, user.checkin_count
+            );
+            if let Err(e) = self.user_service.record_checkin(user.id).await {
+                error!("记录主动关怀失败: user_id={}, error={}", user.id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn checkin_due(&self, user: &User, now: DateTime<Utc>) -> bool {
+        let Some(inactive) = self.checkin.inactive else {
+            return false;
+        };
+
+        if user.relation == UserRelation::Stranger
+            || !user.greetings_enabled
+            || user.checkin_count >= self.checkin.max_unanswered
+            || self.quiet_until(user).is_some()
+        {
+            return false;
+        }
+
+        let local_hour = now.with_timezone(&self.timezone_of(user)).hour();
+        if local_hour < CHECKIN_HOURS.0 || local_hour > CHECKIN_HOURS.1 {
+            return false;
+        }
 
+        // 没有活跃记录的老用户不发送，避免升级后集中打扰
+        let Some(last_active) = user.last_active_at.as_deref().and_then(parse_db_datetime) else {
+            return false;
+        };
+        if now - last_active < inactive {
+            return false;
+        }
+
+        user.last_checkin_at
+            .as_deref()
+            .and_then(parse_db_datetime)
+            .is_none_or(|last| now - last >= self.checkin.cooldown)
+    }
+
+    /// now 由调用方传入，定时任务里使用当前时间，测试中可以模拟第二天凌晨
+    async fn regenerate_random_tasks(&self, now: DateTime<Utc>) -> Result<()> {
+        let users = self.user_service.get_all_users().await?;
+
+        for user in users {
+            if user.relation == UserRelation::Stranger || !user.greetings_enabled {
+                continue;
+            }
+
+            let local_hour = now.with_timezone(&self.timezone_of(&user)).hour();
+            if local_hour != 1 {
+                continue;
+            }
+
+            info!("执行每日随机任务重新生成: user_id={}", user.id);
+            if let Err(e) = self.reset_random_tasks_for_user(&user, now).await {
+                error!("重新生成随机任务失败: user_id={}, error={}", user.id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 移除用户现有的系统任务（包括调度器中的任务），并按问候目录重新生成 now 所在当天的问候
+    async fn reset_random_tasks_for_user(&self, user: &User, now: DateTime<Utc>) -> Result<()> {
+        for task in self.service.get_system_tasks_for_user(user.id).await? {
+            self.unschedule_task(task.id).await;
+        }
+        self.service.delete_system_tasks_for_user(user.id).await?;
+
+        if user.relation == UserRelation::Stranger || !user.greetings_enabled {
+            return Ok(());
+        }
+
+        self.create_random_tasks_for_user(user, now).await
+    }
+
+    async fn create_random_tasks_for_user(&self, user: &User, now: DateTime<Utc>) -> Result<()> {
+        let catalogue = self.greetings.current();
+        let timezone = self.timezone_of(user);
+
+        for req in greeting_requests(&catalogue, user, timezone, now) {
             let task = self.service.create_task(req).await?;
 
-            self.schedule_task(
-                task.id,
-                &task.cron_expr,
-                task.frequency,
-                user_id,
-                task.content,
-            )
-            .await?;
+            self.schedule_task(&task).await?;
         }
 
         Ok(())
     }
 
-    pub async fn schedule_task(
-        &self,
-        task_id: i64,
-        cron_expr: &str,
-        frequency: TaskFrequency,
-        target_user_id: i64,
-        content: String,
-    ) -> Result<()> {
-        let service = self.service.clone();
-        let task_tx = self.task_tx.clone();
+    pub async fn schedule_task(&self, task: &ScheduledTask) -> Result<()> {
+        let task_id = task.id;
+        let cron_expr = task.cron_expr.as_str();
+        let frequency = task.frequency;
+        let target_user_id = task.target_user_id;
+        let content = task.content.clone();
+        let calendar = task.calendar;
+        let start_at = task.start_at.as_deref().and_then(parse_db_datetime);
 
-        let job = Job::new_async(cron_expr, move |uuid, lock| {
-            let service = service.clone();
-            let task_tx = task_tx.clone();
+        let dispatcher = self.dispatcher();
+        let jobs = Arc::clone(&self.jobs);
+        let cron = cron_expr.to_string();
+        let timezone = self.timezone_for(target_user_id).await;
+
+        let job = Job::new_async_tz(cron_expr, timezone, move |uuid, lock| {
+            let dispatcher = dispatcher.clone();
+            let jobs = Arc::clone(&jobs);
             let content = content.clone();
+            let scheduled_at = current_fire_time(&cron, timezone);
 
             Box::pin(async move {
                 debug!("定时任务触发: id={}", task_id);
 
+                // 导入的重复日程可能从将来某天才开始，之前的触发直接跳过
+                if start_at.is_some_and(|start_at| scheduled_at < start_at) {
+                    debug!("任务尚未到开始时间，跳过本次触发: id={}", task_id);
+                    return;
+                }
+
                 let agent_task = AgentTask {
+                    task_id: Some(task_id),
                     target_user_id,
                     content,
+                    scheduled_at,
+                    attempt: 0,
                 };
+                let finished = dispatcher
+                    .dispatch(agent_task, calendar, frequency, timezone)
+                    .await;
 
-                if let Err(e) = task_tx.send(agent_task).await {
-                    error!("发送任务到 Agent 失败: {}", e);
-                }
-
-                if let Err(e) = service.update_last_run(task_id).await {
-                    error!("更新任务执行时间失败: {}", e);
-                }
-
-                if frequency == TaskFrequency::Once {
-                    if let Err(e) = service.disable_task(task_id).await {
-                        error!("禁用一次性任务失败: {}", e);
-                    }
+                if finished {
+                    jobs.lock().await.remove(&task_id);
 
                     if let Err(e) = lock.remove(&uuid).await {
-                        error!("从调度器移除一次性任务失败: {}", e);
+                        error!("从调度器移除已结束任务失败: {}", e);
                     } else {
-                        debug!("已从调度器移除一次性任务: id={}", task_id);
+                        debug!("已从调度器移除已结束任务: id={}", task_id);
                     }
                 }
             })
         })?;
 
-        self.scheduler.lock().await.add(job).await?;
-        debug!("任务已添加到调度器: id={}, cron={}", task_id, cron_expr);
+        let uuid = self.scheduler.lock().await.add(job).await?;
+        let old_uuid = self.jobs.lock().await.insert(task_id, uuid);
+        if let Some(old_uuid) = old_uuid {
+            self.remove_job(task_id, old_uuid).await;
+        }
+
+        let workdays = (calendar == TaskCalendar::CnWorkday).then(|| self.holidays.current());
+        let next_run_at = compute_next_run_at(cron_expr, timezone, workdays.as_deref(), start_at);
+        if let Err(e) = self
+            .service
+            .update_next_run(task_id, next_run_at.as_deref())
+            .await
+        {
+            error!("更新任务下次执行时间失败: id={}, error={}", task_id, e);
+        }
+        debug!(
+            "任务已添加到调度器: id={}, cron={}, timezone={}",
+            task_id, cron_expr, timezone
+        );
 
         Ok(())
     }
 
-    pub async fn add_task(&self, req: CreateTaskRequest) -> Result<ScheduledTask> {
+    fn dispatcher(&self) -> TaskDispatcher {
+        TaskDispatcher {
+            service: self.service.clone(),
+            task_run_service: self.task_run_service.clone(),
+            queue: self.queue.clone(),
+            holidays: Arc::clone(&self.holidays),
+        }
+    }
+
+    pub fn default_timezone(&self) -> Tz {
+        self.default_timezone
+    }
+
+    pub async fn timezone_for(&self, user_id: i64) -> Tz {
+        match self.user_service.get_user(user_id).await {
+            Ok(Some(user)) => self.timezone_of(&user),
+            Ok(None) => self.default_timezone,
+            Err(e) => {
+                error!("查询用户时区失败: user_id={}, error={}", user_id, e);
+                self.default_timezone
+            }
+        }
+    }
+
+    pub fn timezone_of(&self, user: &User) -> Tz {
+        user.timezone
+            .as_deref()
+            .and_then(|tz| parse_timezone(tz).ok())
+            .unwrap_or(self.default_timezone)
+    }
+
+    /// 用户当前处于免打扰或安静时段时，返回可以打扰的时间
+    pub fn quiet_until(&self, user: &User) -> Option<DateTime<Utc>> {
+        let now = Utc::now();
+        let dnd_until = user
+            .dnd_until
+            .as_deref()
+            .and_then(parse_db_datetime)
+            .filter(|until| *until > now);
+        let window_end = quiet_window_end(user, self.timezone_of(user), now);
+
+        dnd_until.into_iter().chain(window_end).max()
+    }
+
+    pub async fn set_user_timezone(&self, user_id: i64, timezone: &str) -> Result<User> {
+        let tz = parse_timezone(timezone)?;
+
+        let user = self
+            .user_service
+            .update_timezone(UpdateTimezoneRequest {
+                id: user_id,
+                timezone: tz.name().to_string(),
+            })
+            .await?;
+
+        let tasks = self.service.get_enabled_tasks().await?;
+        for task in tasks.into_iter().filter(|t| t.target_user_id == user_id) {
+            if let Err(e) = self.schedule_task(&task).await {
+                error!("按新时区重新调度任务失败: id={}, error={}", task.id, e);
+            }
+        }
+        info!("用户时区已更新: user_id={}, timezone={}", user_id, tz);
+
+        Ok(user)
+    }
+
+    async fn unschedule_task(&self, task_id: i64) {
+        let uuid = self.jobs.lock().await.remove(&task_id);
+
+        if let Some(uuid) = uuid {
+            self.remove_job(task_id, uuid).await;
+        }
+    }
+
+    async fn remove_job(&self, task_id: i64, uuid: Uuid) {
+        match self.scheduler.lock().await.remove(&uuid).await {
+            Ok(_) => debug!("任务已从调度器移除: id={}, uuid={}", task_id, uuid),
+            Err(e) => error!("从调度器移除任务失败: id={}, error={}", task_id, e),
+        }
+    }
+
+    pub fn greeting_categories(&self) -> Vec<String> {
+        self.greetings.current().categories()
+    }
+
+    /// 更新用户的问候偏好，并按新偏好重新生成当天的问候任务
+    pub async fn set_greeting_preference(
+        &self,
+        user_id: i64,
+        enabled: bool,
+        categories: Option<Vec<String>>,
+    ) -> Result<User> {
+        if let Some(categories) = &categories {
+            let available = self.greeting_categories();
+            if let Some(unknown) = categories.iter().find(|c| !available.contains(c)) {
+                bail!(
+                    "未知的问候类别: {}，可选类别: {}",
+                    unknown,
+                    available.join(", ")
+                );
+            }
+        }
+
+        let user = self
+            .user_service
+            .update_greeting_preference(UpdateGreetingPreferenceRequest {
+                id: user_id,
+                greetings_enabled: enabled,
+                greeting_categories: categories.map(|c| c.join(",")),
+            })
+            .await?;
+
+        self.reset_random_tasks_for_user(&user, Utc::now()).await?;
+        info!(
+            "用户问候偏好已更新: user_id={}, enabled={}, categories={:?}",
+            user_id, user.greetings_enabled, user.greeting_categories
+        );
+
+        Ok(user)
+    }
+
+    pub async fn add_task(&self, mut req: CreateTaskRequest) -> Result<ScheduledTask> {
+        if req.calendar == TaskCalendar::CnWorkday {
+            req.cron_expr = workday_cron(req.frequency, &req.cron_expr)?;
+        }
+        validate_frequency(
+            schedule_frequency(req.frequency, req.calendar),
+            &req.cron_expr,
+        )?;
+        validate_end_condition(req.end_at.as_deref(), req.max_runs)?;
+        if req.max_retries.is_some_and(|n| n < 0) {
+            bail!("重试次数不能为负数");
+        }
+        validate_context(req.context.as_deref())?;
+        if req.created_by == TaskCreator::User {
+            self.check_task_creation(&req).await?;
+        }
+
         let task = self.service.create_task(req).await?;
 
-        self.schedule_task(
-            task.id,
-            &task.cron_expr,
-            task.frequency,
-            task.target_user_id,
-            task.content.clone(),
-        )
-        .await?;
+        self.schedule_task(&task).await?;
+
+        self.service
+            .get_task(task.id)
+            .await?
+            .ok_or_else(|| anyhow!("任务 ID {} 不存在", task.id))
+    }
+
+    pub async fn list_tasks(&self, user_id: i64) -> Result<Vec<ScheduledTask>> {
+        self.service.get_tasks_for_user(user_id).await
+    }
+
+    pub async fn recent_failures(&self, operator_id: i64, limit: i64) -> Result<Vec<TaskRun>> {
+        if !self.user_service.is_master(operator_id).await? {
+            bail!("只有 master 可以查看任务执行失败记录");
+        }
+
+        self.task_run_service.get_recent_failures(limit).await
+    }
+
+    pub async fn list_today_tasks(&self, user_id: i64) -> Result<Vec<ScheduledTask>> {
+        let timezone = self.timezone_for(user_id).await;
+        let end_of_today = Utc::now()
+            .with_timezone(&timezone)
+            .date_naive()
+            .and_hms_opt(23, 59, 59)
+            .and_then(|t| t.and_local_timezone(timezone).latest())
+            .ok_or_else(|| anyhow!("无法计算今日结束时间"))?;
+
+        self.service
+            .get_upcoming_tasks_for_user(user_id, &format_db_datetime(&end_of_today.to_utc()))
+            .await
+    }
+
+    pub async fn cancel_task(&self, operator_id: i64, task_id: i64) -> Result<ScheduledTask> {
+        let task = self.get_owned_task(operator_id, task_id).await?;
+
+        self.unschedule_task(task_id).await;
+        self.service.delete_task(task_id).await?;
+        info!("定时任务已取消: id={}", task_id);
+
+        Ok(task)
+    }
+
+    pub async fn pause_task(&self, operator_id: i64, task_id: i64) -> Result<ScheduledTask> {
+        let task = self.get_owned_task(operator_id, task_id).await?;
+        if !task.enabled {
+            bail!("任务 {} 已处于暂停状态", task_id);
+        }
+
+        self.unschedule_task(task_id).await;
+        self.service.disable_task(task_id).await?;
+        info!("定时任务已暂停: id={}", task_id);
+
+        self.get_owned_task(operator_id, task_id).await
+    }
+
+    pub async fn resume_task(&self, operator_id: i64, task_id: i64) -> Result<ScheduledTask> {
+        let task = self.get_owned_task(operator_id, task_id).await?;
+        if task.enabled {
+            bail!("任务 {} 未被暂停", task_id);
+        }
+        let timezone = self.timezone_for(task.target_user_id).await;
+        if end_condition_met(&task, timezone) {
+            bail!(
+                "任务 {} 已满足结束条件，请先修改截止时间或执行次数",
+                task_id
+            );
+        }
+        // 一次性任务的 Cron 表达式没有年份，过了执行时间再恢复会变成明年的同一天
+        if task.frequency == TaskFrequency::Once
+            && (task.run_count > 0
+                || once_fire_time(&task, timezone).is_none_or(|at| at <= Utc::now()))
+        {
+            bail!(
+                "一次性任务 {} 的执行时间已经过去，无法恢复，请重新创建提醒",
+                task_id
+            );
+        }
+        if let Some(creator_id) = task.creator_user_id {
+            self.check_task_quota(creator_id).await?;
+        }
+
+        self.service.enable_task(task_id).await?;
+        self.schedule_task(&task).await?;
+        info!("定时任务已恢复: id={}", task_id);
+
+        self.get_owned_task(operator_id, task_id).await
+    }
+
+    pub async fn update_task(
+        &self,
+        operator_id: i64,
+        mut req: UpdateTaskRequest,
+    ) -> Result<ScheduledTask> {
+        let current = self.get_owned_task(operator_id, req.task_id).await?;
+
+        let reschedule =
+            req.cron_expr.is_some() || req.frequency.is_some() || req.calendar.is_some();
+        match req.calendar.unwrap_or(current.calendar) {
+            TaskCalendar::CnWorkday => {
+                let frequency = req.frequency.unwrap_or(current.frequency);
+                let cron_expr = req.cron_expr.as_deref().unwrap_or(&current.cron_expr);
+                req.cron_expr = Some(workday_cron(frequency, cron_expr)?);
+            }
+            // 工作日任务的星期字段已被改为每天，改回普通日历时需要重新指定执行时间
+            TaskCalendar::Any
+                if current.calendar == TaskCalendar::CnWorkday && req.cron_expr.is_none() =>
+            {
+                bail!("取消工作日日历时请同时提供新的 Cron 表达式");
+            }
+            TaskCalendar::Any => {}
+        }
+        if reschedule {
+            validate_frequency(
+                schedule_frequency(
+                    req.frequency.unwrap_or(current.frequency),
+                    req.calendar.unwrap_or(current.calendar),
+                ),
+                req.cron_expr.as_deref().unwrap_or(&current.cron_expr),
+            )?;
+        }
+        validate_end_condition(
+            req.end_at.clone().flatten().as_deref(),
+            req.max_runs.flatten(),
+        )?;
+        if req.max_retries.flatten().is_some_and(|n| n < 0) {
+            bail!("重试次数不能为负数");
+        }
+        validate_context(req.context.clone().flatten().as_deref())?;
+
+        let task = self.service.update_task(req).await?;
+
+        if task.enabled {
+            self.schedule_task(&task).await?;
+        }
+        info!("定时任务已更新: id={}", task.id);
+
+        self.service
+            .get_task(task.id)
+            .await?
+            .ok_or_else(|| anyhow!("任务 ID {} 不存在", task.id))
+    }
+
+    /// 记录发给用户的提醒消息，用于识别用户对提醒的回复
+    pub async fn record_reminder_messages(
+        &self,
+        user_id: i64,
+        task_id: i64,
+        message_seqs: Vec<i64>,
+    ) -> Result<()> {
+        if message_seqs.is_empty() {
+            return Ok(());
+        }
+
+        self.reminder_message_service
+            .create_messages(CreateReminderMessagesRequest {
+                user_id,
+                task_id,
+                message_seqs,
+            })
+            .await
+    }
+
+    /// 处理用户对提醒的快捷回复（稍后提醒、完成、取消），返回给用户的确认消息；
+    /// 不是对提醒的回复时返回 None
+    pub async fn handle_reminder_reply(
+        &self,
+        user_id: i64,
+        quoted_seq: Option<i64>,
+        text: &str,
+    ) -> Result<Option<String>> {
+        let Some(reply) = parse_reminder_reply(text, quoted_seq.is_some()) else {
+            return Ok(None);
+        };
+
+        // 没有引用提醒时，“好”“收到”之类的短回复很可能只是在聊天，交给正常对话处理
+        if quoted_seq.is_none() && reply == ReminderReply::Done {
+            return Ok(None);
+        }
+
+        let message = match quoted_seq {
+            Some(seq) => {
+                self.reminder_message_service
+                    .get_by_seq(user_id, seq)
+                    .await?
+            }
+            None => {
+                let since = format_db_datetime(&(Utc::now() - self.reminder_reply_window));
+                self.reminder_message_service
+                    .get_latest_pending(user_id, &since)
+                    .await?
+            }
+        };
+        let Some(message) = message else {
+            return Ok(None);
+        };
+
+        info!(
+            "收到提醒回复: user_id={}, task_id={}, reply={:?}",
+            user_id, message.task_id, reply
+        );
+
+        let task = self.service.get_task(message.task_id).await?;
+        let reply_message = match reply {
+            ReminderReply::Snooze(delay) => {
+                let Some(task) = task else {
+                    return Ok(Some("这条提醒对应的任务已经不存在了".to_string()));
+                };
+
+                let timezone = self.timezone_for(user_id).await;
+                let at = Utc::now().with_timezone(&timezone) + delay;
+                self.add_task(CreateTaskRequest {
+                    target_user_id: user_id,
+                    frequency: TaskFrequency::Once,
+                    cron_expr: once_cron(&at),
+                    content: task.content,
+                    context: task.context,
+                    created_by: TaskCreator::User,
+                    creator_user_id: Some(user_id),
+                    start_at: None,
+                    end_at: None,
+                    max_runs: None,
+                    misfire_policy: MisfirePolicy::RunImmediately,
+                    max_retries: task.max_retries,
+                    urgent: task.urgent,
+                    calendar: TaskCalendar::Any,
+                })
+                .await?;
+
+                self.reminder_message_service
+                    .acknowledge(user_id, message.task_id, ReminderAck::Snoozed)
+                    .await?;
+                format!("好的，{} 再提醒你", at.format("%H:%M"))
+            }
+            ReminderReply::Done => {
+                self.reminder_message_service
+                    .acknowledge(user_id, message.task_id, ReminderAck::Done)
+                    .await?;
+                "好的，这次提醒已完成".to_string()
+            }
+            // 取消会删除整个任务，没有引用提醒时只提示用户确认，不直接取消
+            ReminderReply::Cancel if quoted_seq.is_none() => match task {
+                Some(task) => format!(
+                    "要取消提醒「{}」吗？请引用那条提醒消息回复“取消”确认",
+                    task.content
+                ),
+                None => "这条提醒对应的任务已经不存在了".to_string(),
+            },
+            ReminderReply::Cancel => {
+                if task.is_some() {
+                    self.cancel_task(user_id, message.task_id).await?;
+                }
+                self.reminder_message_service
+                    .acknowledge(user_id, message.task_id, ReminderAck::Cancelled)
+                    .await?;
+                "已取消这个提醒，之后不会再提醒你了".to_string()
+            }
+        };
+
+        Ok(Some(reply_message))
+    }
+
+    /// 导出用户启用中的提醒为 iCalendar 文件
+    pub async fn export_calendar(&self, user_id: i64) -> Result<CalendarExport> {
+        let timezone = self.timezone_for(user_id).await;
+        let tasks: Vec<ScheduledTask> = self
+            .service
+            .get_tasks_for_user(user_id)
+            .await?
+            .into_iter()
+            .filter(|task| task.enabled && task.created_by == TaskCreator::User)
+            .collect();
+
+        let export = render_calendar(&tasks, timezone);
+        info!(
+            "导出日历: user_id={}, exported={}, skipped={}",
+            user_id, export.exported, export.skipped
+        );
+        Ok(export)
+    }
+
+    /// 解析用户发来的日历文件并生成预览，确认后才会创建任务
+    pub async fn preview_import(&self, user_id: i64, content: &str) -> Result<String> {
+        let timezone = self.timezone_for(user_id).await;
+        let events = parse_calendar(content, timezone)?;
+        if events.is_empty() {
+            bail!("日历文件中没有事件");
+        }
+        if events.len() > MAX_IMPORT_EVENTS {
+            bail!("一次最多导入 {} 个事件", MAX_IMPORT_EVENTS);
+        }
+
+        let mut requests = Vec::new();
+        let mut lines = Vec::new();
+
+        for event in events {
+            let title: String = event.summary.chars().take(30).collect();
+            let task = event.task.and_then(|task| {
+                validate_frequency(task.frequency, &task.cron_expr)?;
+                let end_at = task.end_at.as_ref().map(format_db_datetime);
+                validate_end_condition(end_at.as_deref(), task.max_runs)?;
+                Ok((task, end_at))
+            });
+
+            match task {
+                Ok((task, end_at)) => {
+                    lines.push(format!(
+                        "{}. {} {} 开始: {}",
+                        requests.len() + 1,
+                        task.frequency.as_str(),
+                        title,
+                        task.first_run.format("%Y-%m-%d %H:%M")
+                    ));
+
+                    let mut content = format!("请用自然、友好的方式提醒用户：{}", event.summary);
+                    if let Some(description) = &event.description {
+                        content.push_str(&format!("\n备注：{}", description));
+                    }
+                    requests.push(CreateTaskRequest {
+                        target_user_id: user_id,
+                        frequency: task.frequency,
+                        cron_expr: task.cron_expr,
+                        content,
+                        context: None,
+                        created_by: TaskCreator::User,
+                        creator_user_id: Some(user_id),
+                        start_at: task.start_at.as_ref().map(format_db_datetime),
+                        end_at,
+                        max_runs: task.max_runs,
+                        misfire_policy: MisfirePolicy::RunImmediately,
+                        max_retries: None,
+                        urgent: false,
+                        calendar: TaskCalendar::Any,
+                    });
+                }
+                Err(e) => lines.push(format!("跳过: {} - {}", title, e)),
+            }
+        }
+
+        let count = requests.len();
+        if count == 0 {
+            bail!("没有可以导入的事件:\n{}", lines.join("\n"));
+        }
+
+        self.pending_imports.lock().await.insert(
+            user_id,
+            PendingImport {
+                requests,
+                expires_at: Utc::now() + TimeDelta::minutes(IMPORT_CONFIRM_MINUTES),
+            },
+        );
+        debug!("日历导入等待确认: user_id={}, count={}", user_id, count);
+
+        Ok(format!(
+            "将导入 {} 个提醒（{}）:\n{}\n\n{} 分钟内发送 #import confirm 确认导入，#import cancel 取消",
+            count,
+            timezone,
+            lines.join("\n"),
+            IMPORT_CONFIRM_MINUTES
+        ))
+    }
+
+    /// 创建等待确认的导入任务，返回成功和失败的数量说明
+    pub async fn confirm_import(&self, user_id: i64) -> Result<String> {
+        let pending = self
+            .pending_imports
+            .lock()
+            .await
+            .remove(&user_id)
+            .filter(|pending| pending.expires_at > Utc::now())
+            .ok_or_else(|| anyhow!("没有待确认的日历导入，请重新发送 .ics 文件"))?;
+
+        let total = pending.requests.len();
+        let mut failures = Vec::new();
+        for req in pending.requests {
+            if let Err(e) = self.add_task(req).await {
+                failures.push(e.to_string());
+            }
+        }
+        info!(
+            "日历导入完成: user_id={}, total={}, failed={}",
+            user_id,
+            total,
+            failures.len()
+        );
+
+        let failed = failures.len();
+        let mut message = format!("已导入 {} 个提醒", total - failed);
+        if failed > 0 {
+            failures.dedup();
+            message.push_str(&format!("，{} 个导入失败: {}", failed, failures.join("；")));
+        }
+        Ok(message)
+    }
+
+    pub async fn cancel_import(&self, user_id: i64) -> bool {
+        self.pending_imports.lock().await.remove(&user_id).is_some()
+    }
+
+    /// 查询操作者有权管理的任务
+    pub async fn get_owned_task(&self, operator_id: i64, task_id: i64) -> Result<ScheduledTask> {
+        let task = self
+            .service
+            .get_task(task_id)
+            .await?
+            .ok_or_else(|| anyhow!("任务 ID {} 不存在", task_id))?;
+
+        if task.created_by != TaskCreator::User {
+            bail!("无权操作任务 {}", task_id);
+        }
+
+        // 任务的创建者、接收者和 master 可以管理任务
+        let allowed = task.target_user_id == operator_id
+            || task.creator_user_id == Some(operator_id)
+            || self.user_service.is_master(operator_id).await?;
+        if !allowed {
+            bail!("无权操作任务 {}", task_id);
+        }
 
         Ok(task)
     }
+
+    async fn check_task_creation(&self, req: &CreateTaskRequest) -> Result<()> {
+        let creator_id = req
+            .creator_user_id
+            .ok_or_else(|| anyhow!("用户任务缺少创建者"))?;
+
+        if creator_id != req.target_user_id {
+            if !self.user_service.is_master(creator_id).await? {
+                bail!("只有 master 可以为其他用户创建任务");
+            }
+            if self
+                .user_service
+                .get_user(req.target_user_id)
+                .await?
+                .is_none()
+            {
+                bail!("目标用户 {} 不存在", req.target_user_id);
+            }
+        }
+
+        self.check_task_quota(creator_id).await
+    }
+
+    /// 检查用户创建的启用任务是否已达到其关系对应的上限
+    async fn check_task_quota(&self, creator_id: i64) -> Result<()> {
+        let creator = self
+            .user_service
+            .get_user(creator_id)
+            .await?
+            .ok_or_else(|| anyhow!("用户 ID {} 不存在", creator_id))?;
+
+        let limit = match creator.relation {
+            UserRelation::Master => self.task_quota.master,
+            UserRelation::Guest => self.task_quota.guest,
+            UserRelation::Stranger => self.task_quota.stranger,
+        };
+        if limit == 0 {
+            return Ok(());
+        }
+
+        let active = self
+            .service
+            .count_active_tasks_created_by(creator_id)
+            .await?;
+        if active >= limit as i64 {
+            bail!(
+                "已达到可同时启用的定时任务上限（{} 个），请先取消或暂停不需要的任务",
+                limit
+            );
+        }
+
+        Ok(())
+    }
 }
 
-async fn create_random_tasks(service: &SchedulerService, user_id: i64) -> Result<()> {
-    for random_task in RANDOM_TASKS {
-        let cron_expr = generate_random_cron(random_task.hour_range);
+/// 按问候目录生成用户当天剩余时段的问候任务
+fn greeting_requests(
+    catalogue: &GreetingCatalogue,
+    user: &User,
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Vec<CreateTaskRequest> {
+    let now = now.with_timezone(&timezone);
+    let today = now.date_naive();
+    let categories: Vec<String> = user
+        .greeting_categories
+        .as_deref()
+        .map(|c| {
+            c.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
 
-        let req = CreateTaskRequest {
-            target_user_id: user_id,
+    catalogue
+        .plan_for(user, &categories, today)
+        .into_iter()
+        .filter(|greeting| greeting.time > now.time())
+        .inspect(|greeting| {
+            debug!(
+                "计划问候: user_id={}, category={}, time={}",
+                user.id, greeting.category, greeting.time
+            );
+        })
+        .map(|greeting| CreateTaskRequest {
+            target_user_id: user.id,
             frequency: TaskFrequency::Once,
-            cron_expr,
-            content: random_task.content.to_string(),
+            cron_expr: format!(
+                "0 {} {} {} {} *",
+                greeting.time.minute(),
+                greeting.time.hour(),
+                today.day(),
+                today.month()
+            ),
+            content: greeting.content,
+            context: None,
             created_by: TaskCreator::System,
+            creator_user_id: None,
+            start_at: None,
+            end_at: None,
+            max_runs: None,
+            misfire_policy: MisfirePolicy::Expire,
+            max_retries: None,
+            urgent: false,
+            calendar: TaskCalendar::Any,
+        })
+        .collect()
+}
+
+fn checkin_content(user: &User, now: DateTime<Utc>) -> String {
+    let days = user
+        .last_active_at
+        .as_deref()
+        .and_then(parse_db_datetime)
+        .map(|last| (now - last).num_days())
+        .unwrap_or_default();
+
+    let mut content = format!(
+        "用户已经 {} 天没有和你说话了，请结合你们之前聊过的内容，用自然、不打扰的语气主动关心一下用户，不要提及具体的天数。",
+        days
+    );
+    if let Some(last_message) = &user.last_message {
+        content.push_str(&format!("\n用户最后一次说的话: {}", last_message));
+    }
+    if user.checkin_count > 0 {
+        content.push_str("\n之前的关心用户还没有回复，这次请更简短、轻松一些。");
+    }
+    content
+}
+
+impl TaskDispatcher {
+    /// 投递任务到 Agent 并更新触发次数，返回任务是否已结束
+    async fn dispatch(
+        &self,
+        agent_task: AgentTask,
+        calendar: TaskCalendar,
+        frequency: TaskFrequency,
+        timezone: Tz,
+    ) -> bool {
+        let Some(task_id) = agent_task.task_id else {
+            return true;
         };
 
-        service.create_task(req).await?;
+        let workdays = (calendar == TaskCalendar::CnWorkday).then(|| self.holidays.current());
+        let fire_date = agent_task
+            .scheduled_at
+            .with_timezone(&timezone)
+            .date_naive();
+
+        if workdays.as_ref().is_some_and(|w| !w.is_workday(fire_date)) {
+            info!("非工作日，跳过本次执行: id={}, date={}", task_id, fire_date);
+        } else {
+            // 以任务 ID 和计划触发时间作为幂等键，补发和正常触发不会重复执行
+            let key = format!(
+                "task:{}:{}",
+                task_id,
+                format_db_datetime(&agent_task.scheduled_at)
+            );
+            let target_user_id = agent_task.target_user_id;
+            let scheduled_at = agent_task.scheduled_at;
+            match self.queue.push(key, agent_task).await {
+                Ok(true) => {
+                    if let Err(e) = self.service.increment_run_count(task_id).await {
+                        error!("更新任务触发次数失败: {}", e);
+                    }
+                }
+                Ok(false) => debug!("任务本次触发已在队列中: id={}", task_id),
+                Err(e) => {
+                    // 本次触发没有真正投递，任务保持启用，重启后可按错过策略补发
+                    error!("写入任务队列失败: id={}, error={}", task_id, e);
+                    self.record_queue_error(task_id, target_user_id, scheduled_at, &e)
+                        .await;
+                    return false;
+                }
+            }
+        }
+
+        let task = match frequency {
+            TaskFrequency::Once => None,
+            _ => match self.service.get_task(task_id).await {
+                Ok(task) => task,
+                Err(e) => {
+                    error!("查询任务失败: id={}, error={}", task_id, e);
+                    return false;
+                }
+            },
+        };
+
+        match task {
+            Some(task) if !end_condition_met(&task, timezone) => {
+                let start_at = task.start_at.as_deref().and_then(parse_db_datetime);
+                let next_run_at =
+                    compute_next_run_at(&task.cron_expr, timezone, workdays.as_deref(), start_at);
+                if let Err(e) = self
+                    .service
+                    .update_next_run(task_id, next_run_at.as_deref())
+                    .await
+                {
+                    error!("更新任务下次执行时间失败: id={}, error={}", task_id, e);
+                }
+                false
+            }
+            _ => {
+                if let Err(e) = self.service.disable_task(task_id).await {
+                    error!("禁用已结束任务失败: {}", e);
+                }
+                true
+            }
+        }
+    }
+
+    /// 记录一次投递失败的执行，让它出现在失败列表中
+    async fn record_queue_error(
+        &self,
+        task_id: i64,
+        target_user_id: i64,
+        scheduled_at: DateTime<Utc>,
+        error: &anyhow::Error,
+    ) {
+        let req = CreateTaskRunRequest {
+            task_id: Some(task_id),
+            target_user_id,
+            attempt: 0,
+            status: TaskRunStatus::QueueError,
+            error: Some(error.to_string()),
+            scheduled_at: format_db_datetime(&scheduled_at),
+            started_at: format_db_datetime(&Utc::now()),
+            latency_ms: 0,
+        };
+        if let Err(e) = self.task_run_service.create_run(req).await {
+            error!("记录任务执行失败: id={}, error={}", task_id, e);
+        }
+    }
+}
+
+/// 触发回调中计算本次的计划触发时间，用于生成稳定的幂等键
+fn current_fire_time(cron_expr: &str, timezone: Tz) -> DateTime<Utc> {
+    let now = Utc::now();
+    let since = (now - TimeDelta::seconds(30)).with_timezone(&timezone);
+
+    next_fire_time(cron_expr, &since)
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .filter(|t| *t <= now)
+        .unwrap_or(now)
+}
+
+/// 计算下次执行时间，指定工作日日历时跳过非工作日的触发
+fn compute_next_run_at(
+    cron_expr: &str,
+    timezone: Tz,
+    workdays: Option<&HolidayCalendar>,
+    start_at: Option<DateTime<Utc>>,
+) -> Option<String> {
+    let after = fire_search_start(start_at).with_timezone(&timezone);
+    let mut next = next_fire_time(cron_expr, &after).ok()?;
+
+    if let Some(workdays) = workdays {
+        for _ in 0..MAX_NON_WORKDAY_SKIPS {
+            if workdays.is_workday(next.date_naive()) {
+                break;
+            }
+            next = next_fire_time(cron_expr, &next).ok()?;
+        }
+    }
+
+    Some(format_db_datetime(&next.with_timezone(&Utc)))
+}
+
+/// 查找下次触发的起点：任务还没开始时从开始时间算起（包含开始时间本身）
+fn fire_search_start(start_at: Option<DateTime<Utc>>) -> DateTime<Utc> {
+    let now = Utc::now();
+    start_at
+        .map(|start_at| start_at - TimeDelta::seconds(1))
+        .filter(|after| *after > now)
+        .unwrap_or(now)
+}
+
+/// 工作日日历只适用于每天或工作日执行的任务，星期字段改为每天以便调休的周末也能触发
+fn workday_cron(frequency: TaskFrequency, cron_expr: &str) -> Result<String> {
+    if !matches!(frequency, TaskFrequency::Daily | TaskFrequency::Weekdays) {
+        bail!("只有每天或工作日执行的任务可以使用工作日日历");
+    }
+
+    every_day_cron(cron_expr)
+}
+
+/// 工作日日历任务的星期字段已被改为每天，按每天检查 Cron 表达式
+fn schedule_frequency(frequency: TaskFrequency, calendar: TaskCalendar) -> TaskFrequency {
+    match calendar {
+        TaskCalendar::CnWorkday => TaskFrequency::Daily,
+        TaskCalendar::Any => frequency,
+    }
+}
+
+/// 一次性任务的执行时间，创建时保证在一年以内，所以从创建时间往后找第一次触发
+fn once_fire_time(task: &ScheduledTask, timezone: Tz) -> Option<DateTime<Utc>> {
+    let created_at = parse_db_datetime(&task.created_at)?;
+    next_fire_time(&task.cron_expr, &created_at.with_timezone(&timezone))
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+/// 计算任务在停机期间错过的最近一次触发时间
+fn missed_fire_time(task: &ScheduledTask, timezone: Tz) -> Option<DateTime<Utc>> {
+    let now = Utc::now();
+
+    let due_at = match task.next_run_at.as_deref().and_then(parse_db_datetime) {
+        Some(next_run_at) => next_run_at,
+        None => {
+            let reference = task
+                .last_run_at
+                .as_deref()
+                .or(Some(task.created_at.as_str()))
+                .and_then(parse_db_datetime)?;
+            let reference = match task.start_at.as_deref().and_then(parse_db_datetime) {
+                Some(start_at) => reference.max(start_at - TimeDelta::seconds(1)),
+                None => reference,
+            };
+            next_fire_time(&task.cron_expr, &reference.with_timezone(&timezone))
+                .ok()?
+                .with_timezone(&Utc)
+        }
+    };
+
+    if due_at >= now {
+        return None;
+    }
+
+    let mut missed_at = due_at;
+    while let Ok(next) = next_fire_time(&task.cron_expr, &missed_at.with_timezone(&timezone)) {
+        let next = next.with_timezone(&Utc);
+        if next >= now {
+            break;
+        }
+        missed_at = next;
+    }
+
+    Some(missed_at)
+}
+
+fn quiet_window_end(user: &User, timezone: Tz, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let start = NaiveTime::parse_from_str(user.quiet_start.as_deref()?, "%H:%M").ok()?;
+    let end = NaiveTime::parse_from_str(user.quiet_end.as_deref()?, "%H:%M").ok()?;
+    let local = now.with_timezone(&timezone);
+    let time = local.time();
+    let today = local.date_naive();
+
+    let end_date = if start < end {
+        if time < start || time >= end {
+            return None;
+        }
+        today
+    } else if start > end {
+        if time >= start {
+            today.succ_opt()?
+        } else if time < end {
+            today
+        } else {
+            return None;
+        }
+    } else {
+        return None;
+    };
+
+    end_date
+        .and_time(end)
+        .and_local_timezone(timezone)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn validate_end_condition(end_at: Option<&str>, max_runs: Option<i64>) -> Result<()> {
+    if let Some(end_at) = end_at {
+        let end_at =
+            parse_db_datetime(end_at).ok_or_else(|| anyhow!("无效的结束时间: {}", end_at))?;
+        if end_at <= Utc::now() {
+            bail!("结束时间必须晚于当前时间");
+        }
+    }
+
+    if let Some(max_runs) = max_runs
+        && max_runs <= 0
+    {
+        bail!("执行次数必须大于 0");
     }
 
     Ok(())
 }
 
-fn generate_random_cron(hour_range: (u32, u32)) -> String {
-    let mut rng = rand::rng();
-    let hour = rng.random_range(hour_range.0..=hour_range.1);
-    let minute = rng.random_range(0..60);
-    format!("0 {} {} * * *", minute, hour)
+fn validate_context(context: Option<&str>) -> Result<()> {
+    if context.is_some_and(|c| c.chars().count() > MAX_TASK_CONTEXT_CHARS) {
+        bail!(
+            "对话背景不能超过 {} 个字符，请只保留关键信息",
+            MAX_TASK_CONTEXT_CHARS
+        );
+    }
+
+    Ok(())
+}
+
+fn end_condition_met(task: &ScheduledTask, timezone: Tz) -> bool {
+    if task
+        .max_runs
+        .is_some_and(|max_runs| task.run_count >= max_runs)
+    {
+        return true;
+    }
+
+    match task.end_at.as_deref().and_then(parse_db_datetime) {
+        Some(end_at) => {
+            let start_at = task.start_at.as_deref().and_then(parse_db_datetime);
+            let after = fire_search_start(start_at).with_timezone(&timezone);
+            match next_fire_time(&task.cron_expr, &after) {
+                Ok(next) => next.with_timezone(&Utc) > end_at,
+                Err(_) => true,
+            }
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::agent_job_service::AgentJobService;
+    use crate::db::init_db;
+    use crate::db::user_model::CreateUserRequest;
+    use sqlx::SqlitePool;
+    use std::time::Duration;
+
+    const USER_ID: i64 = 10001;
+
+    /// 模拟的时钟：第一天和第二天的凌晨1点（Asia/Shanghai），第二天取真实的明天，
+    /// 这样第二天的问候时间都在未来，调度器给出的下次触发时间就是计划的时间
+    fn simulated_days(timezone: Tz) -> (DateTime<Utc>, DateTime<Utc>) {
+        let today = Utc::now().with_timezone(&timezone).date_naive();
+        let one_am = |date: chrono::NaiveDate| {
+            date.and_hms_opt(1, 0, 0)
+                .and_then(|t| t.and_local_timezone(timezone).single())
+                .map(|t| t.to_utc())
+                .expect("凌晨1点应当是唯一的本地时间")
+        };
+        (one_am(today), one_am(today + TimeDelta::days(1)))
+    }
+
+    async fn live_jobs(manager: &SchedulerManager) -> HashMap<i64, Uuid> {
+        manager.jobs.lock().await.clone()
+    }
+
+    async fn next_tick(manager: &SchedulerManager, uuid: Uuid) -> Option<DateTime<Utc>> {
+        manager
+            .scheduler
+            .lock()
+            .await
+            .next_tick_for_job(uuid)
+            .await
+            .expect("查询下次触发时间失败")
+    }
+
+    /// 调度器的删除是异步处理的，等待任务从调度器中消失
+    async fn wait_removed(manager: &SchedulerManager, uuid: Uuid) -> bool {
+        for _ in 0..50 {
+            if next_tick(manager, uuid).await.is_none() {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        false
+    }
+
+    async fn test_manager(pool: &SqlitePool) -> Result<SchedulerManager> {
+        let config = SchedulerConfig {
+            greetings_path: "nonexistent-greetings.toml".to_string(),
+            holidays_path: "nonexistent-holidays.toml".to_string(),
+            ..SchedulerConfig::default()
+        };
+        SchedulerManager::new(
+            SchedulerService::new(pool.clone()),
+            UserService::new(pool.clone()),
+            TaskRunService::new(pool.clone()),
+            ReminderMessageService::new(pool.clone()),
+            JobQueue::new(AgentJobService::new(pool.clone())),
+            &config,
+        )
+        .await
+    }
+
+    async fn create_test_user(pool: &SqlitePool) -> Result<User> {
+        UserService::new(pool.clone())
+            .create_user(CreateUserRequest {
+                id: USER_ID,
+                name: "test".to_string(),
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn greetings_are_regenerated_and_fire_on_day_two() -> Result<()> {
+        let pool = init_db("sqlite::memory:", 1).await?;
+        let agent_job_service = AgentJobService::new(pool.clone());
+        let manager = test_manager(&pool).await?;
+        let user = create_test_user(&pool).await?;
+        let timezone = manager.timezone_of(&user);
+        let (day_one, day_two) = simulated_days(timezone);
+
+        // 第一天生成的问候
+        manager.reset_random_tasks_for_user(&user, day_one).await?;
+        let yesterday = live_jobs(&manager).await;
+        assert!(!yesterday.is_empty(), "第一天应当生成问候任务");
+
+        // 第二天凌晨1点的每日重新生成
+        manager.regenerate_random_tasks(day_two).await?;
+
+        for uuid in yesterday.values() {
+            assert!(
+                wait_removed(&manager, *uuid).await,
+                "昨天的任务 {} 应当已从调度器移除",
+                uuid
+            );
+        }
+
+        let tasks = manager.service.get_system_tasks_for_user(USER_ID).await?;
+        let today = live_jobs(&manager).await;
+        assert!(!tasks.is_empty(), "第二天应当生成问候任务");
+        assert_eq!(tasks.len(), today.len());
+
+        for task in &tasks {
+            assert!(!yesterday.contains_key(&task.id));
+            let uuid = today
+                .get(&task.id)
+                .copied()
+                .unwrap_or_else(|| panic!("任务 {} 没有注册到调度器", task.id));
+            assert!(!yesterday.values().any(|old| *old == uuid));
+
+            let fire_at = next_tick(&manager, uuid)
+                .await
+                .unwrap_or_else(|| panic!("任务 {} 没有下次触发时间", task.id));
+            assert_eq!(
+                fire_at.with_timezone(&timezone).date_naive(),
+                day_two.with_timezone(&timezone).date_naive(),
+                "任务 {} 应当在第二天触发",
+                task.id
+            );
+            assert_eq!(Some(format_db_datetime(&fire_at)), task.next_run_at);
+        }
+
+        // 把调度器的时间拨到第一条问候的触发时间，确认任务真的会执行并投递到队列
+        let task = &tasks[0];
+        let uuid = today[&task.id];
+        let scheduler = manager.scheduler.lock().await.clone();
+        scheduler.start().await?;
+        scheduler
+            .context()
+            .metadata_storage
+            .write()
+            .await
+            .set_next_and_last_tick(uuid, Some(Utc::now()), None)
+            .await?;
+
+        let mut queued = None;
+        for _ in 0..50 {
+            let far_future = format_db_datetime(&(Utc::now() + TimeDelta::days(366)));
+            queued = agent_job_service.claim_next(&far_future).await?;
+            if queued.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let queued = queued.expect("第二天的问候应当触发并写入任务队列");
+        assert_eq!(queued.task_id, Some(task.id));
+        assert_eq!(queued.target_user_id, USER_ID);
+
+        assert!(
+            wait_removed(&manager, uuid).await,
+            "一次性问候执行后应当移除"
+        );
+        manager.shutdown().await;
+
+        Ok(())
+    }
+
+    /// 暂停的一次性提醒在执行时间过去后不能恢复，否则会在明年同一天触发
+    #[tokio::test]
+    async fn paused_once_task_cannot_resume_after_fire_time() -> Result<()> {
+        let pool = init_db("sqlite::memory:", 1).await?;
+        let manager = test_manager(&pool).await?;
+        let user = create_test_user(&pool).await?;
+        let timezone = manager.timezone_of(&user);
+
+        let create_paused_once = |at: DateTime<Utc>| {
+            let manager = &manager;
+            async move {
+                let task = manager
+                    .add_task(CreateTaskRequest {
+                        target_user_id: USER_ID,
+                        frequency: TaskFrequency::Once,
+                        cron_expr: once_cron(&at.with_timezone(&timezone)),
+                        content: "test".to_string(),
+                        context: None,
+                        created_by: TaskCreator::User,
+                        creator_user_id: Some(USER_ID),
+                        start_at: None,
+                        end_at: None,
+                        max_runs: None,
+                        misfire_policy: MisfirePolicy::RunImmediately,
+                        max_retries: None,
+                        urgent: false,
+                        calendar: TaskCalendar::Any,
+                    })
+                    .await?;
+                manager.pause_task(USER_ID, task.id).await
+            }
+        };
+
+        // 执行时间还没到的提醒可以正常恢复
+        let upcoming = create_paused_once(Utc::now() + TimeDelta::hours(1)).await?;
+        let resumed = manager.resume_task(USER_ID, upcoming.id).await?;
+        assert!(resumed.enabled);
+
+        // 模拟暂停期间时间流逝：把任务的创建时间和执行时间都挪到两天前
+        let missed = create_paused_once(Utc::now() + TimeDelta::hours(1)).await?;
+        let fire_at = Utc::now() - TimeDelta::days(2);
+        sqlx::query("UPDATE scheduled_tasks SET cron_expr = ?, created_at = ? WHERE id = ?")
+            .bind(once_cron(&fire_at.with_timezone(&timezone)))
+            .bind(format_db_datetime(&(fire_at - TimeDelta::hours(1))))
+            .bind(missed.id)
+            .execute(&pool)
+            .await?;
+
+        assert!(manager.resume_task(USER_ID, missed.id).await.is_err());
+        let task = manager
+            .service
+            .get_task(missed.id)
+            .await?
+            .expect("任务应当仍然存在");
+        assert!(!task.enabled);
+        assert!(!manager.jobs.lock().await.contains_key(&missed.id));
+
+        manager.shutdown().await;
+        Ok(())
+    }
+
+    /// 写入队列失败时任务保持启用，并留下一条失败的执行记录
+    #[tokio::test]
+    async fn queue_failure_keeps_task_enabled_and_records_run() -> Result<()> {
+        let pool = init_db("sqlite::memory:", 1).await?;
+        let manager = test_manager(&pool).await?;
+        let user = create_test_user(&pool).await?;
+        let timezone = manager.timezone_of(&user);
+
+        let fire_at = Utc::now() + TimeDelta::hours(1);
+        let task = manager
+            .add_task(CreateTaskRequest {
+                target_user_id: USER_ID,
+                frequency: TaskFrequency::Once,
+                cron_expr: once_cron(&fire_at.with_timezone(&timezone)),
+                content: "test".to_string(),
+                context: None,
+                created_by: TaskCreator::User,
+                creator_user_id: Some(USER_ID),
+                start_at: None,
+                end_at: None,
+                max_runs: None,
+                misfire_policy: MisfirePolicy::RunImmediately,
+                max_retries: None,
+                urgent: false,
+                calendar: TaskCalendar::Any,
+            })
+            .await?;
+
+        sqlx::query("DROP TABLE agent_jobs").execute(&pool).await?;
+
+        let agent_task = AgentTask {
+            task_id: Some(task.id),
+            target_user_id: USER_ID,
+            content: task.content.clone(),
+            scheduled_at: fire_at,
+            attempt: 0,
+        };
+        let finished = manager
+            .dispatcher()
+            .dispatch(agent_task, task.calendar, task.frequency, timezone)
+            .await;
+        assert!(!finished);
+
+        let task = manager
+            .service
+            .get_task(task.id)
+            .await?
+            .expect("任务应当仍然存在");
+        assert!(task.enabled);
+        assert_eq!(task.run_count, 0);
+
+        let failures = manager.task_run_service.get_recent_failures(10).await?;
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].task_id, Some(task.id));
+        assert_eq!(failures[0].status, TaskRunStatus::QueueError);
+
+        manager.shutdown().await;
+        Ok(())
+    }
 }
@@ -0,0 +1,177 @@
+use crate::agent::AgentTask;
+use crate::db::agent_job_model::{
+    AgentJob, AgentJobStatus, CreateAgentJobRequest, RescheduleAgentJobRequest,
+};
+use crate::db::agent_job_service::AgentJobService;
+use crate::utils::{format_db_datetime, parse_db_datetime};
+use anyhow::Result;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{error, info};
+
+/// 队列为空时的最长等待时间，防止错过通知
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 已结束的队列任务保留天数
+const FINISHED_JOB_RETENTION_DAYS: i64 = 7;
+
+pub struct QueuedJob {
+    pub job_id: i64,
+    pub available_at: DateTime<Utc>,
+    pub task: AgentTask,
+}
+
+/// 基于 SQLite 的 Agent 任务队列，任务先落库再通知执行器，进程崩溃后可以恢复
+#[derive(Clone)]
+pub struct JobQueue {
+    service: AgentJobService,
+    notify: Arc<Notify>,
+}
+
+impl JobQueue {
+    pub fn new(service: AgentJobService) -> Self {
+        Self {
+            service,
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    /// 恢复上次退出时未执行完的任务，并清理过期的历史任务
+    pub async fn recover(&self) -> Result<()> {
+        let recovered = self.service.recover_running().await?;
+        if recovered > 0 {
+            info!("已恢复 {} 个中断的队列任务", recovered);
+        }
+
+        let before = Utc::now() - TimeDelta::days(FINISHED_JOB_RETENTION_DAYS);
+        self.service
+            .delete_finished_before(&format_db_datetime(&before))
+            .await?;
+
+        Ok(())
+    }
+
+    /// 写入队列，相同幂等键的任务只会执行一次
+    pub async fn push(&self, idempotency_key: String, task: AgentTask) -> Result<bool> {
+        let inserted = self
+            .service
+            .enqueue(CreateAgentJobRequest {
+                idempotency_key,
+                task_id: task.task_id,
+                target_user_id: task.target_user_id,
+                content: task.content,
+                scheduled_at: format_db_datetime(&task.scheduled_at),
+                attempt: task.attempt as i64,
+            })
+            .await?;
+
+        if inserted {
+            self.notify.notify_one();
+        }
+        Ok(inserted)
+    }
+
+    pub async fn reschedule(
+        &self,
+        job_id: i64,
+        attempt: u32,
+        available_at: DateTime<Utc>,
+    ) -> Result<()> {
+        self.service
+            .reschedule(RescheduleAgentJobRequest {
+                id: job_id,
+                attempt: attempt as i64,
+                available_at: format_db_datetime(&available_at),
+            })
+            .await?;
+
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    pub async fn finish(&self, job_id: i64, status: AgentJobStatus, error: Option<&str>) {
+        if let Err(e) = self.service.finish(job_id, status, error).await {
+            error!("更新队列任务状态失败: id={}, error={}", job_id, e);
+        }
+
+        // 同一用户排在后面的任务此时可以被取出
+        self.notify.notify_one();
+    }
+
+    /// 当前已到期但尚未开始执行的任务数
+    pub async fn depth(&self) -> i64 {
+        self.service
+            .count_due(&format_db_datetime(&Utc::now()))
+            .await
+            .unwrap_or_else(|e| {
+                error!("查询任务队列长度失败: {}", e);
+                0
+            })
+    }
+
+    /// 等待并取出下一个到期的任务
+    pub async fn next(&self) -> QueuedJob {
+        loop {
+            let now = Utc::now();
+
+            match self.service.claim_next(&format_db_datetime(&now)).await {
+                Ok(Some(job)) => match Self::to_queued(&job) {
+                    Some(queued) => return queued,
+                    None => {
+                        self.finish(job.id, AgentJobStatus::Failed, Some("任务时间格式错误"))
+                            .await;
+                        continue;
+                    }
+                },
+                Ok(None) => {}
+                Err(e) => error!("读取任务队列失败: {}", e),
+            }
+
+            // 到期任务可能因同一用户的任务正在执行而暂时无法取出，此时至少等待一秒
+            let wait = match self.service.next_available_at().await {
+                Ok(Some(available_at)) => parse_db_datetime(&available_at)
+                    .and_then(|at| (at - now).to_std().ok())
+                    .unwrap_or_default()
+                    .clamp(MIN_POLL_INTERVAL, IDLE_POLL_INTERVAL),
+                Ok(None) => IDLE_POLL_INTERVAL,
+                Err(e) => {
+                    error!("查询任务队列失败: {}", e);
+                    IDLE_POLL_INTERVAL
+                }
+            };
+
+            tokio::select! {
+                _ = self.notify.notified() => {}
+                _ = tokio::time::sleep(wait) => {}
+            }
+        }
+    }
+
+    fn to_queued(job: &AgentJob) -> Option<QueuedJob> {
+        let (Some(scheduled_at), Some(available_at)) = (
+            parse_db_datetime(&job.scheduled_at),
+            parse_db_datetime(&job.available_at),
+        ) else {
+            error!(
+                "队列任务时间格式错误，已丢弃: id={}, scheduled_at={}, available_at={}",
+                job.id, job.scheduled_at, job.available_at
+            );
+            return None;
+        };
+
+        Some(QueuedJob {
+            job_id: job.id,
+            available_at,
+            task: AgentTask {
+                task_id: job.task_id,
+                target_user_id: job.target_user_id,
+                content: job.content.clone(),
+                scheduled_at,
+                attempt: job.attempt.max(0) as u32,
+            },
+        })
+    }
+}
@@ -0,0 +1,217 @@
+use chrono::TimeDelta;
+use std::ops::Range;
+
+/// 用户对提醒的快捷回复
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReminderReply {
+    Snooze(TimeDelta),
+    Done,
+    Cancel,
+}
+
+/// 超过这个长度的消息视为正常聊天，不按快捷回复处理
+const MAX_REPLY_CHARS: usize = 20;
+
+const DEFAULT_SNOOZE_MINUTES: i64 = 10;
+
+const CANCEL_KEYWORDS: &[&str] = &[
+    "cancel",
+    "取消",
+    "别提醒",
+    "不用提醒",
+    "不要提醒",
+    "不要再提醒",
+];
+
+const SNOOZE_KEYWORDS: &[&str] = &[
+    "snooze",
+    "later",
+    "稍后",
+    "等会",
+    "等一下",
+    "等下",
+    "晚点",
+    "过会",
+    "推迟",
+    "再提醒",
+];
+
+const DONE_REPLIES: &[&str] = &[
+    "done",
+    "ok",
+    "okay",
+    "好",
+    "好的",
+    "好了",
+    "完成",
+    "完成了",
+    "已完成",
+    "做完了",
+    "知道了",
+    "收到",
+];
+
+/// 没有引用提醒时，除了关键词和时长之外回复里只能出现这些字
+const REPLY_FILLERS: &[&str] = &[
+    "提醒我",
+    "提醒",
+    "一会儿",
+    "一会",
+    "以后",
+    "之后",
+    "后",
+    "再",
+    "我",
+    "吧",
+    "了",
+    "请",
+    "，",
+    ",",
+    " ",
+];
+
+const CHINESE_DIGITS: &str = "零一二两三四五六七八九十";
+
+/// 解析对提醒的快捷回复。没有引用提醒时只接受关键词加可选时长的回复（如 "等下"、
+/// "半小时后再提醒"），避免把 "等下我去吃饭" 这样的聊天当成稍后提醒
+pub fn parse_reminder_reply(text: &str, quoted: bool) -> Option<ReminderReply> {
+    let text = text
+        .trim()
+        .trim_end_matches(['。', '！', '!', '.', '~', '～'])
+        .to_lowercase();
+
+    if text.is_empty() || text.chars().count() > MAX_REPLY_CHARS {
+        return None;
+    }
+
+    if let Some(rest) = strip_keyword(&text, CANCEL_KEYWORDS) {
+        return (quoted || only_fillers(&rest)).then_some(ReminderReply::Cancel);
+    }
+
+    if let Some(mut rest) = strip_keyword(&text, SNOOZE_KEYWORDS) {
+        let delay = parse_snooze_delay(&rest).map(|(delay, range)| {
+            rest.replace_range(range, "");
+            delay
+        });
+        if !quoted && !only_fillers(&rest) {
+            return None;
+        }
+        let delay = delay.unwrap_or_else(|| TimeDelta::minutes(DEFAULT_SNOOZE_MINUTES));
+        return Some(ReminderReply::Snooze(delay));
+    }
+
+    DONE_REPLIES
+        .contains(&text.as_str())
+        .then_some(ReminderReply::Done)
+}
+
+/// 去掉文本中第一个出现的关键词，没有关键词时返回 None
+fn strip_keyword(text: &str, keywords: &[&str]) -> Option<String> {
+    keywords
+        .iter()
+        .find(|k| text.contains(*k))
+        .map(|k| text.replacen(k, "", 1))
+}
+
+fn only_fillers(text: &str) -> bool {
+    REPLY_FILLERS
+        .iter()
+        .fold(text.to_string(), |rest, filler| rest.replace(filler, ""))
+        .is_empty()
+}
+
+/// 解析 "10分钟"、"两个小时"、"半小时"、"30m" 等时长，单位缺省为分钟；
+/// 同时返回时长在文本中的字节范围
+fn parse_snooze_delay(text: &str) -> Option<(TimeDelta, Range<usize>)> {
+    for half in ["半个小时", "半小时"] {
+        if let Some(start) = text.find(half) {
+            return Some((TimeDelta::try_minutes(30)?, start..start + half.len()));
+        }
+    }
+
+    let is_number = |c: char| c.is_ascii_digit() || CHINESE_DIGITS.contains(c);
+    let start = text.find(is_number)?;
+    let end = text[start..]
+        .find(|c| !is_number(c))
+        .map_or(text.len(), |len| start + len);
+
+    let number = &text[start..end];
+    let amount = number
+        .parse::<i64>()
+        .ok()
+        .or_else(|| parse_chinese_number(number))?;
+
+    let unit = text[end..].trim_start();
+    let unit_start = text.len() - unit.len();
+    let unit_len = |units: &[&str]| units.iter().find(|u| unit.starts_with(*u)).map(|u| u.len());
+    let (delay, len) = if let Some(len) = unit_len(&["个小时", "小时", "h"]) {
+        (TimeDelta::try_hours(amount)?, len)
+    } else if let Some(len) = unit_len(&["天", "d"]) {
+        (TimeDelta::try_days(amount)?, len)
+    } else {
+        let len = unit_len(&["分钟", "min", "分", "m"]).unwrap_or(0);
+        (TimeDelta::try_minutes(amount)?, len)
+    };
+
+    (delay > TimeDelta::zero() && delay <= TimeDelta::try_days(7)?)
+        .then_some((delay, start..unit_start + len))
+}
+
+/// 解析一百以内的中文数字
+fn parse_chinese_number(value: &str) -> Option<i64> {
+    let digit = |c: char| match c {
+        '零' => Some(0),
+        '一' => Some(1),
+        '二' | '两' => Some(2),
+        '三' => Some(3),
+        '四' => Some(4),
+        '五' => Some(5),
+        '六' => Some(6),
+        '七' => Some(7),
+        '八' => Some(8),
+        '九' => Some(9),
+        _ => None,
+    };
+
+    let chars: Vec<char> = value.chars().collect();
+    match chars.as_slice() {
+        [c] if *c == '十' => Some(10),
+        [c] => digit(*c),
+        ['十', c] => digit(*c).map(|n| 10 + n),
+        [c, '十'] => digit(*c).map(|n| n * 10),
+        [a, '十', b] => Some(digit(*a)? * 10 + digit(*b)?),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 没有引用提醒时，只有关键词和时长组成的回复才算稍后提醒或取消
+    #[test]
+    fn unquoted_replies_require_bare_keyword() {
+        let snooze = |minutes| Some(ReminderReply::Snooze(TimeDelta::minutes(minutes)));
+
+        assert_eq!(parse_reminder_reply("等下", false), snooze(10));
+        assert_eq!(parse_reminder_reply("等一下", false), snooze(10));
+        assert_eq!(parse_reminder_reply("晚点再提醒我", false), snooze(10));
+        assert_eq!(parse_reminder_reply("半小时后再提醒", false), snooze(30));
+        assert_eq!(parse_reminder_reply("推迟两个小时", false), snooze(120));
+        assert_eq!(parse_reminder_reply("snooze 15m", false), snooze(15));
+        assert_eq!(
+            parse_reminder_reply("不要再提醒了", false),
+            Some(ReminderReply::Cancel)
+        );
+
+        assert_eq!(parse_reminder_reply("等下我去吃饭", false), None);
+        assert_eq!(parse_reminder_reply("see you later", false), None);
+        assert_eq!(parse_reminder_reply("帮我取消明天的会", false), None);
+
+        assert_eq!(parse_reminder_reply("等下我去吃饭", true), snooze(10));
+        assert_eq!(
+            parse_reminder_reply("帮我取消明天的会", true),
+            Some(ReminderReply::Cancel)
+        );
+    }
+}
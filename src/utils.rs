@@ -1,3 +1,6 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use milky_rust_sdk::MilkyClient;
 use milky_rust_sdk::prelude::{OutgoingSegment, TextData};
 use std::sync::Arc;
@@ -12,3 +15,66 @@ pub async fn send_message(milky_client: Arc<MilkyClient>, sender: i64, messages:
         }
     }
 }
+
+pub async fn send_group_message(
+    milky_client: Arc<MilkyClient>,
+    group_id: i64,
+    messages: Vec<String>,
+) {
+    for msg in messages {
+        let segments = vec![OutgoingSegment::Text(TextData { text: msg })];
+
+        if let Err(e) = milky_client.send_group_message(group_id, segments).await {
+            error!("发送群消息失败: {}", e);
+        }
+    }
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz> {
+    name.trim().parse::<Tz>().map_err(|_| {
+        anyhow!(
+            "无效的时区: {}，请使用 IANA 时区名，例如 Asia/Shanghai",
+            name
+        )
+    })
+}
+
+const DB_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub fn parse_db_datetime(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, DB_DATETIME_FORMAT)
+        .ok()
+        .map(|t| t.and_utc())
+}
+
+pub fn parse_local_datetime(value: &str, timezone: Tz) -> Option<DateTime<Tz>> {
+    let value = value.trim();
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&timezone));
+    }
+
+    let formats = [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+    ];
+
+    formats
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(value, fmt).ok())
+        .and_then(|naive| timezone.from_local_datetime(&naive).earliest())
+}
+
+pub fn format_local_datetime(value: &str, timezone: Tz) -> Option<String> {
+    parse_db_datetime(value).map(|t| {
+        t.with_timezone(&timezone)
+            .format("%Y-%m-%d %H:%M:%S %A")
+            .to_string()
+    })
+}
+
+pub fn format_db_datetime(value: &DateTime<Utc>) -> String {
+    value.format(DB_DATETIME_FORMAT).to_string()
+}